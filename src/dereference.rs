@@ -0,0 +1,230 @@
+use crate::{
+    document::{absolute_id, resolve_relationship_entry, Service},
+    error::Error,
+    registry::ResolverRegistry,
+    resolution::DID_LD_JSON,
+    DidUrl, Document, DocumentMetadata, Relationship, ResolutionMetadata, VerificationMethod,
+    DEFAULT_REGISTRY,
+};
+
+/// Resource selected by dereferenced DID URL.
+/// DID URL without fragment selects the whole `Document`,
+///     with fragment - single `VerificationMethod` (listed or embedded
+///     in verification relationship) or `Service` of it.
+///
+#[derive(Debug, PartialEq)]
+pub enum DereferencedResource {
+    Document(Document),
+    VerificationMethod(VerificationMethod),
+    Service(Service),
+}
+
+/// Full output of DID URL dereferencing as defined by the spec:
+///     https://w3c-ccg.github.io/did-resolution/#did-url-dereferencing-result
+/// `content_stream` is `None` if dereferencing failed, in which case
+///     `dereferencing_metadata.error` holds the error code.
+///
+#[derive(Debug, Default)]
+pub struct DereferencingResult {
+    pub dereferencing_metadata: ResolutionMetadata,
+    pub content_stream: Option<DereferencedResource>,
+    pub content_metadata: DocumentMetadata,
+}
+
+impl DereferencingResult {
+    fn from_error(error: &Error) -> Self {
+        Self {
+            dereferencing_metadata: ResolutionMetadata {
+                content_type: None,
+                error: Some(error.code().into()),
+            },
+            ..Default::default()
+        }
+    }
+
+    /// `true` if resource was found.
+    ///
+    pub fn is_ok(&self) -> bool {
+        self.content_stream.is_some()
+    }
+}
+
+/// Dereferences `did_url` into the resource it points at with resolvers
+///     of `ResolverRegistry::default()`, see `ResolverRegistry::dereference()`.
+/// Will never fail, errors are reported in `dereferencing_metadata.error`.
+///
+pub fn dereference(did_url: &str) -> DereferencingResult {
+    DEFAULT_REGISTRY.dereference(did_url)
+}
+
+// Dereferences `did_url` with resolvers of `registry`
+pub(crate) fn dereference_with(registry: &ResolverRegistry, did_url: &str) -> DereferencingResult {
+    let url: DidUrl = match did_url.parse() {
+        Ok(url) => url,
+        Err(e) => return DereferencingResult::from_error(&e),
+    };
+    // paths are method specific and none of the resolvers define any
+    if let Some(path) = &url.path {
+        return DereferencingResult::from_error(&Error::NotFound(format!("path {}", path)));
+    }
+    let resolved_url = DidUrl {
+        fragment: None,
+        ..url.clone()
+    }
+    .to_string();
+    let resolution = registry.resolve_with_metadata(&resolved_url);
+    let document = match resolution.did_document {
+        Some(document) => document,
+        None => {
+            return DereferencingResult {
+                dereferencing_metadata: resolution.did_resolution_metadata,
+                ..Default::default()
+            }
+        }
+    };
+    let content = match &url.fragment {
+        None => Some(DereferencedResource::Document(document)),
+        Some(fragment) => match select_verification_method(&document, &url.did(), fragment) {
+            Some(vm) => Some(DereferencedResource::VerificationMethod(vm)),
+            None => match registry.services(&resolved_url) {
+                Ok(services) => select_service(services, &url.did(), fragment)
+                    .map(DereferencedResource::Service),
+                Err(e) => return DereferencingResult::from_error(&e),
+            },
+        },
+    };
+    match content {
+        Some(content) => DereferencingResult {
+            dereferencing_metadata: ResolutionMetadata {
+                content_type: Some(DID_LD_JSON.into()),
+                error: None,
+            },
+            content_stream: Some(content),
+            content_metadata: resolution.did_document_metadata,
+        },
+        None => DereferencingResult::from_error(&Error::NotFound(format!(
+            "fragment {}",
+            url.fragment.unwrap_or_default()
+        ))),
+    }
+}
+
+// Selects verification method with id matching `fragment` of `did`,
+//  either listed in `verification_method` or embedded in one of
+//  verification relationships.
+fn select_verification_method(
+    document: &Document,
+    did: &str,
+    fragment: &str,
+) -> Option<VerificationMethod> {
+    let id = format!("{}#{}", did, fragment);
+    let relationships = [
+        Relationship::Authentication,
+        Relationship::AssertionMethod,
+        Relationship::KeyAgreement,
+        Relationship::CapabilityInvocation,
+        Relationship::CapabilityDelegation,
+    ];
+    document
+        .verification_method
+        .iter()
+        .cloned()
+        .chain(
+            relationships
+                .iter()
+                .filter_map(|r| r.entries(document))
+                .flatten()
+                .filter_map(|entry| resolve_relationship_entry(document, entry)),
+        )
+        .find(|vm| absolute_id(did, &vm.id) == id)
+}
+
+// Selects service with id matching `fragment` of `did`
+fn select_service(services: Vec<Service>, did: &str, fragment: &str) -> Option<Service> {
+    let id = format!("{}#{}", did, fragment);
+    services
+        .into_iter()
+        .find(|service| absolute_id(did, &service.id) == id)
+}
+
+#[cfg(all(test, feature = "didkey"))]
+mod dereference_tests {
+    use super::*;
+
+    const DID: &str = "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+
+    #[test]
+    fn dereference_document_test() {
+        let res = dereference(DID);
+        assert!(res.is_ok());
+        assert!(matches!(
+            res.content_stream,
+            Some(DereferencedResource::Document(_))
+        ));
+    }
+
+    #[test]
+    fn dereference_verification_method_test() {
+        let url = format!("{}#z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp", DID);
+        let res = dereference(&url);
+        assert_eq!(
+            res.dereferencing_metadata.content_type,
+            Some(DID_LD_JSON.into())
+        );
+        match res.content_stream {
+            Some(DereferencedResource::VerificationMethod(vm)) => {
+                assert_eq!(vm.id, url);
+                assert_eq!(vm.key_type, "Ed25519VerificationKey2018");
+            }
+            other => panic!("unexpected content: {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "peer")]
+    #[test]
+    fn dereference_embedded_method_and_service_test() {
+        let (did, _) = crate::peer::numalgo4_did(&serde_json::json!({
+            "@context": ["https://www.w3.org/ns/did/v1"],
+            "authentication": [{
+                "id": "#key-1",
+                "type": "Ed25519VerificationKey2020",
+                "publicKeyMultibase": "z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp"
+            }],
+            "service": [{
+                "id": "#didcomm",
+                "type": "DIDCommMessaging",
+                "serviceEndpoint": "https://example.com/endpoint"
+            }]
+        }));
+        match dereference(&format!("{}#key-1", did)).content_stream {
+            Some(DereferencedResource::VerificationMethod(vm)) => {
+                assert_eq!(vm.id, "#key-1");
+                assert_eq!(vm.key_type, "Ed25519VerificationKey2020");
+            }
+            other => panic!("unexpected content: {:?}", other),
+        }
+        match dereference(&format!("{}#didcomm", did)).content_stream {
+            Some(DereferencedResource::Service(service)) => {
+                assert_eq!(service.service_type, "DIDCommMessaging");
+                assert_eq!(service.service_endpoint, "https://example.com/endpoint");
+            }
+            other => panic!("unexpected content: {:?}", other),
+        }
+        let res = dereference(&format!("{}#key-2", did));
+        assert_eq!(res.dereferencing_metadata.error, Some("notFound".into()));
+    }
+
+    #[test]
+    fn dereference_not_found_test() {
+        let res = dereference(&format!("{}#missing", DID));
+        assert!(!res.is_ok());
+        assert_eq!(res.dereferencing_metadata.error, Some("notFound".into()));
+        let res = dereference(&format!("{}/some/path", DID));
+        assert_eq!(res.dereferencing_metadata.error, Some("notFound".into()));
+        let res = dereference("did:key:");
+        assert_eq!(
+            res.dereferencing_metadata.error,
+            Some("invalidDidUrl".into())
+        );
+    }
+}
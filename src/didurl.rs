@@ -0,0 +1,257 @@
+use crate::Error;
+use std::{fmt, str::FromStr};
+
+/// Parsed DID URL as defined by did-core ABNF:
+///     https://www.w3.org/TR/did-core/#did-url-syntax
+///
+/// `did:<method>:<method_specific_id>[/path][?query][#fragment]`
+///
+/// Percent-encoded triplets are validated but kept as they are,
+///     so `to_string()` gives back exactly the parsed url.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DidUrl {
+    pub method: String,
+    pub method_specific_id: String,
+    /// `path-abempty` including leading "/", `None` if empty.
+    pub path: Option<String>,
+    /// Raw query without leading "?".
+    pub query: Option<String>,
+    /// Fragment without leading "#".
+    pub fragment: Option<String>,
+}
+
+impl DidUrl {
+    /// Plain DID (`did:<method>:<method_specific_id>`) of this url,
+    ///     without path, query and fragment.
+    ///
+    pub fn did(&self) -> String {
+        format!("did:{}:{}", self.method, self.method_specific_id)
+    }
+
+    /// Query split into `name=value` pairs. Parameters without value
+    ///     are returned with empty value.
+    ///
+    pub fn query_params(&self) -> Vec<(&str, &str)> {
+        match &self.query {
+            Some(query) => query
+                .split('&')
+                .filter(|p| !p.is_empty())
+                .map(|p| match p.find('=') {
+                    Some(i) => (&p[..i], &p[i + 1..]),
+                    None => (p, ""),
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Value of first query parameter with `name`, if present.
+    ///
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query_params()
+            .into_iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v)
+    }
+}
+
+impl FromStr for DidUrl {
+    type Err = Error;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| Error::InvalidDidUrl(format!("{}: {}", reason, url));
+        let rest = url
+            .strip_prefix("did:")
+            .ok_or_else(|| invalid("must start with \"did:\""))?;
+
+        // method-name = 1*method-char
+        let method_end = rest
+            .find(':')
+            .ok_or_else(|| invalid("missing method specific id"))?;
+        let method = &rest[..method_end];
+        if method.is_empty()
+            || !method
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        {
+            return Err(invalid("invalid method name"));
+        }
+        let rest = &rest[method_end + 1..];
+
+        // method-specific-id = *( *idchar ":" ) 1*idchar
+        let id_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let method_specific_id = &rest[..id_end];
+        if method_specific_id.is_empty()
+            || method_specific_id.ends_with(':')
+            || !valid_chars(method_specific_id, |b| is_idchar(b) || b == b':')
+        {
+            return Err(invalid("invalid method specific id"));
+        }
+        let rest = &rest[id_end..];
+
+        // path-abempty = *( "/" segment )
+        let path_end = rest.find(['?', '#']).unwrap_or(rest.len());
+        let path = &rest[..path_end];
+        if !valid_chars(path, |b| is_pchar(b) || b == b'/') {
+            return Err(invalid("invalid path"));
+        }
+        let rest = &rest[path_end..];
+
+        // [ "?" query ] [ "#" fragment ]
+        let (query, fragment) = match rest.find('#') {
+            Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+            None => (rest, None),
+        };
+        let query = query.strip_prefix('?');
+        for part in query.iter().chain(fragment.iter()) {
+            if !valid_chars(part, |b| is_pchar(b) || b == b'/' || b == b'?') {
+                return Err(invalid("invalid query or fragment"));
+            }
+        }
+
+        Ok(DidUrl {
+            method: method.into(),
+            method_specific_id: method_specific_id.into(),
+            path: if path.is_empty() {
+                None
+            } else {
+                Some(path.into())
+            },
+            query: query.map(String::from),
+            fragment: fragment.map(String::from),
+        })
+    }
+}
+
+impl fmt::Display for DidUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.did())?;
+        if let Some(path) = &self.path {
+            write!(f, "{}", path)?;
+        }
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+        Ok(())
+    }
+}
+
+// Checks that every byte of `s` is either allowed by `allowed`
+//  or a part of valid pct-encoded triplet.
+fn valid_chars(s: &str, allowed: impl Fn(u8) -> bool) -> bool {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            match bytes.get(i + 1..i + 3) {
+                Some([a, b]) if a.is_ascii_hexdigit() && b.is_ascii_hexdigit() => i += 3,
+                _ => return false,
+            }
+        } else if allowed(bytes[i]) {
+            i += 1;
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+// idchar = ALPHA / DIGIT / "." / "-" / "_" / pct-encoded
+fn is_idchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-' | b'_')
+}
+
+// pchar = unreserved / pct-encoded / sub-delims / ":" / "@"
+fn is_pchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric()
+        || matches!(
+            b,
+            b'-' | b'.'
+                | b'_'
+                | b'~'
+                | b'!'
+                | b'$'
+                | b'&'
+                | b'\''
+                | b'('
+                | b')'
+                | b'*'
+                | b'+'
+                | b','
+                | b';'
+                | b'='
+                | b':'
+                | b'@'
+        )
+}
+
+#[cfg(test)]
+mod did_url_tests {
+    use super::*;
+
+    #[test]
+    fn parse_full_did_url_test() {
+        let url: DidUrl = "did:example:123/path/to?service=agent&versionId=1#key-1"
+            .parse()
+            .unwrap();
+        assert_eq!(url.method, "example");
+        assert_eq!(url.method_specific_id, "123");
+        assert_eq!(url.path, Some("/path/to".into()));
+        assert_eq!(url.query_param("service"), Some("agent"));
+        assert_eq!(url.query_param("versionId"), Some("1"));
+        assert_eq!(url.query_param("missing"), None);
+        assert_eq!(url.fragment, Some("key-1".into()));
+        assert_eq!(url.did(), "did:example:123");
+    }
+
+    #[test]
+    fn parse_method_specific_id_test() {
+        let web: DidUrl = "did:web:example.com%3A3000:user:alice".parse().unwrap();
+        assert_eq!(web.method, "web");
+        assert_eq!(web.method_specific_id, "example.com%3A3000:user:alice");
+        let keri: DidUrl = "did:keri:D1bkcOzM-YwEXKPc5yHbMzkHRrZS3O6QAVEpGsS0XpF_E"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            keri.method_specific_id,
+            "D1bkcOzM-YwEXKPc5yHbMzkHRrZS3O6QAVEpGsS0XpF_E"
+        );
+        // empty segments before the last one are allowed
+        assert!("did:example::123".parse::<DidUrl>().is_ok());
+    }
+
+    #[test]
+    fn reject_invalid_did_url_test() {
+        for url in [
+            "idd:key:z6Mk",
+            "did::z6Mk",
+            "did:KEY:z6Mk",
+            "did:key:",
+            "did:key",
+            "did:web:example.com:",
+            "did:web:exa mple.com",
+            "did:web:example.com%3",
+            "did:web:example.com%zz",
+            "did:key:z6Mk#frag#ment",
+            "thisisnot_a_did",
+        ] {
+            assert!(url.parse::<DidUrl>().is_err(), "{} should be rejected", url);
+        }
+    }
+
+    #[test]
+    fn display_round_trip_test() {
+        for url in [
+            "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp",
+            "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp#z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp",
+            "did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk?kerl=eyJ2Ijoi",
+            "did:web:example.com%3A3000:user:alice/did.json?a=b&c#frag",
+        ] {
+            assert_eq!(url.parse::<DidUrl>().unwrap().to_string(), url);
+        }
+    }
+}
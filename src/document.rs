@@ -0,0 +1,223 @@
+use crate::{Document, Error, KeyFormat, VerificationMethod, JWK};
+use base58::FromBase58;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Verification relationships of DID document, which state what
+///     purpose verification method is authorised for:
+///     https://www.w3.org/TR/did-core/#verification-relationships
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Relationship {
+    Authentication,
+    AssertionMethod,
+    KeyAgreement,
+    CapabilityInvocation,
+    CapabilityDelegation,
+}
+
+impl Relationship {
+    /// Property name of the relationship in JSON representation.
+    ///
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Relationship::Authentication => "authentication",
+            Relationship::AssertionMethod => "assertionMethod",
+            Relationship::KeyAgreement => "keyAgreement",
+            Relationship::CapabilityInvocation => "capabilityInvocation",
+            Relationship::CapabilityDelegation => "capabilityDelegation",
+        }
+    }
+
+    /// Entries of the relationship in `document`, `None` if not present.
+    ///
+    pub fn entries<'a>(&self, document: &'a Document) -> Option<&'a Vec<String>> {
+        match self {
+            Relationship::Authentication => document.authentication.as_ref(),
+            Relationship::AssertionMethod => document.assertion_method.as_ref(),
+            Relationship::KeyAgreement => document.key_agreement.as_ref(),
+            Relationship::CapabilityInvocation => document.capability_invocation.as_ref(),
+            Relationship::CapabilityDelegation => document.capability_delegation.as_ref(),
+        }
+    }
+}
+
+/// Service of DID document:
+///     https://www.w3.org/TR/did-core/#services
+/// `Document` has no services section, so resolvers of methods
+///     which define services expose them with `DdoResolver::services()`.
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Service {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub service_type: String,
+    pub service_endpoint: Value,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routing_keys: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accept: Vec<String>,
+}
+
+// Resolves relationship `entry` into verification method. Entry is either
+//  a reference to one of `verification_method`s, or a method embedded
+//  as JSON object.
+pub(crate) fn resolve_relationship_entry(
+    document: &Document,
+    entry: &str,
+) -> Option<VerificationMethod> {
+    if entry.trim_start().starts_with('{') {
+        let value: Value = serde_json::from_str(entry).ok()?;
+        verification_method_from_json(&value)
+    } else {
+        find_verification_method(document, entry).cloned()
+    }
+}
+
+// Finds verification method by reference, which is either absolute
+//  (`did:...#id`) or relative (`#id`) to the document id.
+pub(crate) fn find_verification_method<'a>(
+    document: &'a Document,
+    reference: &str,
+) -> Option<&'a VerificationMethod> {
+    let reference = absolute_id(&document.id, reference);
+    document
+        .verification_method
+        .iter()
+        .find(|vm| absolute_id(&document.id, &vm.id) == reference)
+}
+
+// Makes relative (`#id`) verification method id absolute to `did`
+pub(crate) fn absolute_id(did: &str, id: &str) -> String {
+    match id.strip_prefix('#') {
+        Some(fragment) => format!("{}#{}", did, fragment),
+        None => id.to_string(),
+    }
+}
+
+// Parses verification method from its standard JSON representation,
+//  which differs from the one `did_key` deserializes.
+// `publicKeyMultibase` is decoded (base58btc only) with multicodec
+//  prefix of the key stripped.
+pub(crate) fn verification_method_from_json(value: &Value) -> Option<VerificationMethod> {
+    let field = |name: &str| value.get(name).and_then(Value::as_str);
+    let public_key = if let Some(key) = field("publicKeyBase58") {
+        Some(KeyFormat::Base58(key.into()))
+    } else if let Some(key) = field("publicKeyMultibase") {
+        Some(KeyFormat::Multibase(decode_multibase_key(key)?))
+    } else if let Some(key) = value.get("publicKeyJwk") {
+        Some(KeyFormat::JWK(
+            serde_json::from_value::<JWK>(key.clone()).ok()?,
+        ))
+    } else {
+        None
+    };
+    Some(VerificationMethod {
+        id: field("id")?.into(),
+        key_type: field("type")?.into(),
+        controller: field("controller").unwrap_or_default().into(),
+        public_key,
+        private_key: None,
+    })
+}
+
+// Known multicodec prefixes of public keys
+const MULTICODEC_PREFIXES: [[u8; 2]; 8] = [
+    [0xed, 0x01], // ed25519-pub
+    [0xec, 0x01], // x25519-pub
+    [0xe7, 0x01], // secp256k1-pub
+    [0xea, 0x01], // bls12_381-g1-pub
+    [0xeb, 0x01], // bls12_381-g2-pub
+    [0xee, 0x01], // bls12_381-g1g2-pub
+    [0x80, 0x24], // p256-pub
+    [0x81, 0x24], // p384-pub
+];
+
+// Multicodec prefix of public keys of verification method `key_type`,
+//  `None` if type does not determine one (e.g. `Multikey`).
+pub(crate) fn multicodec_prefix(key_type: &str) -> Option<[u8; 2]> {
+    match key_type {
+        "Ed25519VerificationKey2018" | "Ed25519VerificationKey2020" => Some([0xed, 0x01]),
+        "X25519KeyAgreementKey2019" | "X25519KeyAgreementKey2020" => Some([0xec, 0x01]),
+        "EcdsaSecp256k1VerificationKey2019" => Some([0xe7, 0x01]),
+        "Bls12381G1Key2020" => Some([0xea, 0x01]),
+        "Bls12381G2Key2020" => Some([0xeb, 0x01]),
+        "P256Key2021" | "EcdsaSecp256r1VerificationKey2019" => Some([0x80, 0x24]),
+        _ => None,
+    }
+}
+
+// Decodes base58btc multibase key and strips its multicodec prefix,
+//  which multibase keys always have. Keys without known prefix are
+//  rejected, as there is no telling raw key bytes apart from them.
+pub(crate) fn decode_multibase_key(key: &str) -> Option<Vec<u8>> {
+    let bytes = key.strip_prefix('z')?.from_base58().ok()?;
+    match bytes.get(..2) {
+        Some(prefix) if MULTICODEC_PREFIXES.iter().any(|known| known == prefix) => {
+            Some(bytes[2..].to_vec())
+        }
+        _ => None,
+    }
+}
+
+/// Parses `Document` from its standard JSON representation:
+///     https://www.w3.org/TR/did-core/#json
+/// Embedded verification methods of relationships are kept as
+///     JSON strings, which `DdoParser` understands.
+/// `@context` array is reduced to its first entry and sections
+///     which `Document` has no place for (e.g. `service`) are ignored.
+///
+pub fn document_from_json(json: &str) -> Result<Document, Error> {
+    let value: Value = serde_json::from_str(json)?;
+    let invalid = |reason: &str| Error::InvalidDocument(reason.into());
+    let context = match value.get("@context") {
+        Some(Value::String(context)) => context.clone(),
+        Some(Value::Array(contexts)) => contexts
+            .first()
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("invalid @context"))?
+            .into(),
+        _ => "https://www.w3.org/ns/did/v1".into(),
+    };
+    let id = value
+        .get("id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("missing id"))?;
+    let verification_method = match value.get("verificationMethod") {
+        Some(Value::Array(methods)) => methods
+            .iter()
+            .map(|vm| {
+                verification_method_from_json(vm)
+                    .ok_or_else(|| invalid("invalid verification method"))
+            })
+            .collect::<Result<Vec<VerificationMethod>, Error>>()?,
+        None => vec![],
+        _ => return Err(invalid("verificationMethod must be an array")),
+    };
+    let relationship = |name: &str| -> Result<Option<Vec<String>>, Error> {
+        match value.get(name) {
+            Some(Value::Array(entries)) => entries
+                .iter()
+                .map(|entry| match entry {
+                    Value::String(reference) => Ok(reference.clone()),
+                    Value::Object(_) => Ok(entry.to_string()),
+                    _ => Err(Error::InvalidDocument(format!("invalid {} entry", name))),
+                })
+                .collect::<Result<Vec<String>, Error>>()
+                .map(Some),
+            None => Ok(None),
+            _ => Err(Error::InvalidDocument(format!("{} must be an array", name))),
+        }
+    };
+    Ok(Document {
+        context,
+        id: id.into(),
+        assertion_method: relationship(Relationship::AssertionMethod.as_str())?,
+        authentication: relationship(Relationship::Authentication.as_str())?,
+        capability_delegation: relationship(Relationship::CapabilityDelegation.as_str())?,
+        capability_invocation: relationship(Relationship::CapabilityInvocation.as_str())?,
+        key_agreement: relationship(Relationship::KeyAgreement.as_str())?,
+        verification_method,
+    })
+}
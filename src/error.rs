@@ -9,12 +9,44 @@ pub enum Error {
     #[error("Only correct did:jolo: URLs are supported")]
     NotDidJolo,
 
+    #[error("invalid DID: {0}")]
+    InvalidDid(String),
+
+    #[error("invalid DID url: {0}")]
+    InvalidDidUrl(String),
+
+    #[error("DID method not supported: {0}")]
+    MethodNotSupported(String),
+
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    #[error("DID is deactivated: {0}")]
+    Deactivated(String),
+
+    #[error("representation not supported: {0}")]
+    RepresentationNotSupported(String),
+
+    #[error("invalid key event log: {0}")]
+    InvalidKel(String),
+
+    #[error("invalid DID document: {0}")]
+    InvalidDocument(String),
+
+    #[deprecated(note = "resolution failures are reported by their own variants")]
     #[error("did_key error: {0}")]
     DidKeyError(String),
 
     #[error("did_keri error: {0}")]
     DidKeriError(String),
 
+    #[error("internal error: {0}")]
+    InternalError(String),
+
+    #[cfg(feature = "didweb")]
+    #[error("HTTP request failed: {0}")]
+    HttpError(String),
+
     #[error("config file opening error: {0}")]
     ConfigOpenError(String),
 
@@ -27,23 +59,27 @@ pub enum Error {
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 
-    #[cfg(feature = "jolo")]
+    #[cfg(feature = "keriox")]
+    #[error(transparent)]
+    SledError(#[from] sled::Error),
+
+    #[cfg(any(feature = "jolo", feature = "ethr"))]
     #[error("Not ETH address. Length must be 20 bytes")]
     NotEthAddress,
 
-    #[cfg(feature = "jolo")]
+    #[cfg(any(feature = "jolo", feature = "ethr"))]
     #[error(transparent)]
     W3Error(#[from] web3::Error),
 
-    #[cfg(feature = "jolo")]
+    #[cfg(any(feature = "jolo", feature = "ethr"))]
     #[error(transparent)]
     W3EthError(#[from] web3::ethabi::Error),
 
-    #[cfg(feature = "jolo")]
+    #[cfg(any(feature = "jolo", feature = "ethr"))]
     #[error(transparent)]
     W3ContractError(#[from] web3::contract::Error),
 
-    #[cfg(feature = "jolo")]
+    #[cfg(any(feature = "jolo", feature = "ethr"))]
     #[error(transparent)]
     FromHexError(#[from] hex::FromHexError),
 
@@ -58,3 +94,26 @@ pub enum Error {
     #[error("Failed to parse IPFS http url: {0}")]
     UriParseError(String),
 }
+
+impl Error {
+    /// Error code string as defined by DID Resolution spec to be
+    ///     reported in `didResolutionMetadata`.
+    /// Invalid KEL does not establish valid `did:keri` identifier,
+    ///     so `InvalidKel` is reported as "invalidDid".
+    ///
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::InvalidDid(_) => "invalidDid",
+            Error::InvalidDidUrl(_) => "invalidDidUrl",
+            Error::MethodNotSupported(_) => "methodNotSupported",
+            Error::InvalidKel(_) => "invalidDid",
+            Error::DidResolutionFailed | Error::NotFound(_) => "notFound",
+            Error::Deactivated(_) => "deactivated",
+            Error::RepresentationNotSupported(_) => "representationNotSupported",
+            Error::InvalidDocument(_) => "invalidDidDocument",
+            #[cfg(feature = "jolo")]
+            Error::NotDidJolo => "invalidDid",
+            _ => "internalError",
+        }
+    }
+}
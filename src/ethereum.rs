@@ -0,0 +1,68 @@
+use crate::Error;
+use tokio::runtime::{Runtime, RuntimeFlavor};
+use web3::{contract::Contract, transports::Http, types::Address, Web3};
+
+// Connects to Ethereum node at `provider_url` over HTTP and binds
+//  contract deployed at `contract_address` with `abi` (JSON) to it.
+pub(crate) fn connect(
+    provider_url: &str,
+    contract_address: &str,
+    abi: &[u8],
+) -> Result<(Web3<Http>, Contract<Http>), Error> {
+    let w3 = Web3::new(Http::new(provider_url)?);
+    let contract = Contract::from_json(w3.eth(), parse_address(contract_address)?, abi)?;
+    Ok((w3, contract))
+}
+
+// Parses hex encoded address with or without `0x` prefix
+pub(crate) fn parse_address(address: &str) -> Result<Address, Error> {
+    let bytes = hex::decode(address.trim_start_matches("0x"))?;
+    if bytes.len() != 20 {
+        return Err(Error::NotEthAddress);
+    }
+    Ok(Address::from_slice(&bytes))
+}
+
+// Blocks on `future` to provide synchronous `DdoResolver` API.
+// If called from within of a multi threaded tokio runtime - reuses it
+//  instead of spinning up a new one, which would panic. Current thread
+//  runtime can not be blocked in place, so `future` is run on a new
+//  runtime in a scoped thread instead.
+pub(crate) fn block_on<F>(future: F) -> Result<F::Output, Error>
+where
+    F: std::future::Future + Send,
+    F::Output: Send,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            Ok(tokio::task::block_in_place(|| handle.block_on(future)))
+        }
+        Ok(_) => std::thread::scope(|scope| {
+            scope
+                .spawn(|| Ok(Runtime::new()?.block_on(future)))
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        }),
+        Err(_) => Ok(Runtime::new()?.block_on(future)),
+    }
+}
+
+#[cfg(test)]
+mod ethereum_tests {
+    use super::*;
+
+    #[test]
+    fn block_on_without_runtime_test() {
+        assert_eq!(block_on(async { 1 }).unwrap(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn block_on_multi_thread_runtime_test() {
+        assert_eq!(block_on(async { 2 }).unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn block_on_current_thread_runtime_test() {
+        assert_eq!(block_on(async { 3 }).unwrap(), 3);
+    }
+}
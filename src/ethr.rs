@@ -0,0 +1,741 @@
+use crate::{
+    document::Service,
+    ethereum::{block_on, connect, parse_address},
+    AsyncDdoResolver, DdoResolver, DidUrl, Document, Error, KeyFormat, Relationship,
+    ResolutionResult, VerificationMethod,
+};
+use async_trait::async_trait;
+use base58::ToBase58;
+use serde_json::Value;
+use web3::{
+    contract::{Contract, Options},
+    ethabi::{RawLog, Token},
+    transports::Http,
+    types::{Address, BlockId, BlockNumber, FilterBuilder, H256, U256, U64},
+    Web3,
+};
+
+/// `did:ethr` resolver, which builds documents from events of ERC-1056
+///     registry contract as defined by
+///     https://github.com/decentralized-identity/ethr-did-resolver/blob/master/doc/did-method-spec.md
+/// Serves single network, which can be any Ethereum node reachable over
+///     HTTP, including local dev chains (anvil, ganache).
+/// Only address identifiers are supported, public key ones
+///     (`did:ethr:0x02...`) fail with `Error::MethodNotSupported`.
+/// `VerificationMethod` has no `blockchainAccountId` property, so
+///     `EcdsaSecp256k1RecoveryMethod2020` methods of the owner and
+///     delegates carry 20 byte account address as `KeyFormat::Multibase`
+///     public key instead.
+/// Not registered in `ResolverRegistry::default()` as it requires node
+///     and registry configuration, use `ResolverRegistry::register()`.
+/// Available ONLY with `ethr` feature
+///
+pub struct DidEthrResolver {
+    w3: Web3<Http>,
+    contract: Contract<Http>,
+    network: String,
+}
+
+// Registry event relevant to the document with its payload decoded
+enum RegistryEvent {
+    Owner {
+        owner: Address,
+    },
+    Delegate {
+        delegate_type: String,
+        delegate: Address,
+        valid_to: U256,
+    },
+    Attribute {
+        name: String,
+        value: Vec<u8>,
+        valid_to: U256,
+    },
+}
+
+// Document with services and block of the latest change
+struct EthrState {
+    document: Document,
+    services: Vec<Service>,
+    version: U256,
+}
+
+impl DidEthrResolver {
+    /// Generic constructor
+    /// #Parameters
+    /// `provider_url` - HTTP endpoint of Ethereum node;
+    /// `registry_address` - hex address of ERC-1056 registry contract;
+    /// `network` - network name as used in DIDs (`did:ethr:<network>:0x...`),
+    ///     DIDs without network name are served by "mainnet" resolver only.
+    ///
+    pub fn new(provider_url: &str, registry_address: &str, network: &str) -> Result<Self, Error> {
+        let (w3, contract) = connect(
+            provider_url,
+            registry_address,
+            include_bytes!("resources/ethr_did_registry.json"),
+        )?;
+        Ok(Self {
+            w3,
+            contract,
+            network: network.into(),
+        })
+    }
+
+    /// Services of `did_url` from `did/svc/*` attributes. `Document` has
+    ///     no services section, so those are only available through this
+    ///     method or `DdoResolver::services()`.
+    ///
+    pub async fn services_async(&self, did_url: &str) -> Result<Vec<Service>, Error> {
+        Ok(self.resolve_state(did_url).await?.services)
+    }
+
+    // Address of the identity in `did_url`, if it belongs to served network
+    fn identity(&self, did_url: &str) -> Result<(String, Address), Error> {
+        let url: DidUrl = did_url.parse()?;
+        if url.method != "ethr" {
+            return Err(Error::InvalidDid(format!("not a did:ethr: {}", did_url)));
+        }
+        let did = url.did();
+        let (network, id) = match url.method_specific_id.rsplit_once(':') {
+            Some((network, id)) => (network, id),
+            None => ("mainnet", url.method_specific_id.as_str()),
+        };
+        if network != self.network {
+            return Err(Error::MethodNotSupported(format!(
+                "did:ethr network {}",
+                network
+            )));
+        }
+        match id.strip_prefix("0x").map(str::len) {
+            Some(40) => Ok((did, parse_address(id)?)),
+            Some(66) => Err(Error::MethodNotSupported(format!(
+                "did:ethr public key identifier {}",
+                did
+            ))),
+            _ => Err(Error::InvalidDid(format!("invalid address: {}", did))),
+        }
+    }
+
+    async fn resolve_state(&self, did_url: &str) -> Result<EthrState, Error> {
+        let (did, identity) = self.identity(did_url)?;
+        let version: U256 = self
+            .contract
+            .query(
+                "changed",
+                (Token::Address(identity),),
+                None,
+                Options::default(),
+                None,
+            )
+            .await?;
+        let history = self.history(identity, version).await?;
+        let now = match self
+            .w3
+            .eth()
+            .block(BlockId::Number(BlockNumber::Latest))
+            .await?
+        {
+            Some(block) => block.timestamp,
+            None => return Err(Error::NotFound("latest block".into())),
+        };
+        let (document, services) = build_document(&did, identity, history, now)?;
+        Ok(EthrState {
+            document,
+            services,
+            version,
+        })
+    }
+
+    // Walks registry events of `identity` back from block `last_change`
+    //  following `previousChange` links, output is in chronological order.
+    async fn history(
+        &self,
+        identity: Address,
+        last_change: U256,
+    ) -> Result<Vec<RegistryEvent>, Error> {
+        let abi = self.contract.abi();
+        let events = [
+            abi.event("DIDOwnerChanged")?,
+            abi.event("DIDDelegateChanged")?,
+            abi.event("DIDAttributeChanged")?,
+        ];
+        let signatures = events.iter().map(|e| e.signature()).collect::<Vec<H256>>();
+        let mut history = vec![];
+        let mut block = last_change;
+        while !block.is_zero() {
+            let number = BlockNumber::Number(U64::from(block.low_u64()));
+            let filter = FilterBuilder::default()
+                .address(vec![self.contract.address()])
+                .topics(
+                    Some(signatures.clone()),
+                    Some(vec![H256::from(identity)]),
+                    None,
+                    None,
+                )
+                .from_block(number)
+                .to_block(number)
+                .build();
+            let mut previous = U256::zero();
+            for log in self.w3.eth().logs(filter).await?.into_iter().rev() {
+                let event = match log.topics.first() {
+                    Some(topic) => events.iter().find(|e| e.signature() == *topic),
+                    None => None,
+                };
+                let event = match event {
+                    Some(event) => event,
+                    None => continue,
+                };
+                let params = event
+                    .parse_log(RawLog {
+                        topics: log.topics,
+                        data: log.data.0,
+                    })?
+                    .params;
+                let param = |name: &str| {
+                    params
+                        .iter()
+                        .find(|p| p.name == name)
+                        .map(|p| p.value.clone())
+                        .ok_or_else(|| {
+                            Error::InvalidDocument(format!("no {} in registry event", name))
+                        })
+                };
+                // events of the same block point to it as previous change
+                if let Some(change) = param("previousChange")?.into_uint() {
+                    if change < block && change > previous {
+                        previous = change;
+                    }
+                }
+                history.push(match event.name.as_str() {
+                    "DIDOwnerChanged" => RegistryEvent::Owner {
+                        owner: param("owner")?.into_address().unwrap_or_default(),
+                    },
+                    "DIDDelegateChanged" => RegistryEvent::Delegate {
+                        delegate_type: bytes32_to_string(param("delegateType")?),
+                        delegate: param("delegate")?.into_address().unwrap_or_default(),
+                        valid_to: param("validTo")?.into_uint().unwrap_or_default(),
+                    },
+                    _ => RegistryEvent::Attribute {
+                        name: bytes32_to_string(param("name")?),
+                        value: param("value")?.into_bytes().unwrap_or_default(),
+                        valid_to: param("validTo")?.into_uint().unwrap_or_default(),
+                    },
+                });
+            }
+            block = previous;
+        }
+        history.reverse();
+        Ok(history)
+    }
+}
+
+impl DdoResolver for DidEthrResolver {
+    fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+        block_on(self.resolve_async(did_url))?
+    }
+
+    fn resolve_with_metadata(&self, did_url: &str) -> ResolutionResult {
+        block_on(self.resolve_with_metadata_async(did_url)).unwrap_or_else(ResolutionResult::from)
+    }
+
+    fn services(&self, did_url: &str) -> Result<Vec<Service>, Error> {
+        block_on(self.services_async(did_url))?
+    }
+}
+
+#[async_trait]
+impl AsyncDdoResolver for DidEthrResolver {
+    async fn resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+        Ok(self.resolve_state(did_url).await?.document)
+    }
+
+    /// Reports block number of the latest registry change as `versionId`.
+    ///
+    async fn resolve_with_metadata_async(&self, did_url: &str) -> ResolutionResult {
+        match self.resolve_state(did_url).await {
+            Ok(state) => {
+                let mut result = ResolutionResult::from_document(state.document);
+                if !state.version.is_zero() {
+                    result.did_document_metadata.version_id = Some(state.version.to_string());
+                }
+                result
+            }
+            Err(e) => e.into(),
+        }
+    }
+}
+
+// Folds registry `history` of `identity` into document and services.
+// Delegates and keys are numbered in order of their events, so ids stay
+//  stable while later events only add or revoke entries.
+fn build_document(
+    did: &str,
+    identity: Address,
+    history: Vec<RegistryEvent>,
+    now: U256,
+) -> Result<(Document, Vec<Service>), Error> {
+    let mut owner = identity;
+    let mut delegate_count = 0;
+    let mut service_count = 0;
+    // (event key, verification method, relationships) of valid entries
+    let mut keys: Vec<(String, VerificationMethod, Vec<Relationship>)> = vec![];
+    let mut services: Vec<(String, Service)> = vec![];
+    for event in history {
+        match event {
+            RegistryEvent::Owner { owner: new_owner } => owner = new_owner,
+            RegistryEvent::Delegate {
+                delegate_type,
+                delegate,
+                valid_to,
+            } => {
+                delegate_count += 1;
+                let key = format!("delegate-{}-{:?}", delegate_type, delegate);
+                keys.retain(|(k, _, _)| *k != key);
+                let relationships = match delegate_type.as_str() {
+                    "veriKey" => vec![Relationship::AssertionMethod],
+                    "sigAuth" => vec![Relationship::AssertionMethod, Relationship::Authentication],
+                    _ => continue,
+                };
+                if valid_to > now {
+                    keys.push((
+                        key,
+                        VerificationMethod {
+                            id: format!("{}#delegate-{}", did, delegate_count),
+                            key_type: "EcdsaSecp256k1RecoveryMethod2020".into(),
+                            controller: did.into(),
+                            public_key: Some(account_key(delegate)),
+                            private_key: None,
+                        },
+                        relationships,
+                    ));
+                }
+            }
+            RegistryEvent::Attribute {
+                name,
+                value,
+                valid_to,
+            } => match name.split('/').collect::<Vec<&str>>()[..] {
+                ["did", "pub", algorithm, purpose, ..] => {
+                    delegate_count += 1;
+                    let key = format!("attribute-{}-{}", name, value.to_base58());
+                    keys.retain(|(k, _, _)| *k != key);
+                    let key_type = match algorithm {
+                        "Secp256k1" => "EcdsaSecp256k1VerificationKey2019",
+                        "Ed25519" => "Ed25519VerificationKey2018",
+                        "X25519" => "X25519KeyAgreementKey2019",
+                        _ => continue,
+                    };
+                    let relationships = match purpose {
+                        "veriKey" => vec![Relationship::AssertionMethod],
+                        "sigAuth" => {
+                            vec![Relationship::AssertionMethod, Relationship::Authentication]
+                        }
+                        "enc" => vec![Relationship::KeyAgreement],
+                        _ => continue,
+                    };
+                    if valid_to > now {
+                        keys.push((
+                            key,
+                            VerificationMethod {
+                                id: format!("{}#delegate-{}", did, delegate_count),
+                                key_type: key_type.into(),
+                                controller: did.into(),
+                                // registry stores raw key, encoding in the name
+                                //  is only a hint for presentation
+                                public_key: Some(KeyFormat::Base58(value.to_base58())),
+                                private_key: None,
+                            },
+                            relationships,
+                        ));
+                    }
+                }
+                ["did", "svc", service_type] => {
+                    service_count += 1;
+                    let key = format!("{}-{}", name, value.to_base58());
+                    services.retain(|(k, _)| *k != key);
+                    if valid_to > now {
+                        let endpoint = String::from_utf8(value)?;
+                        let service_endpoint = match serde_json::from_str::<Value>(&endpoint) {
+                            Ok(json) if json.is_object() || json.is_array() => json,
+                            _ => Value::String(endpoint),
+                        };
+                        services.push((
+                            key,
+                            Service {
+                                id: format!("{}#service-{}", did, service_count),
+                                service_type: service_type.into(),
+                                service_endpoint,
+                                routing_keys: vec![],
+                                accept: vec![],
+                            },
+                        ));
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+    if owner == Address::zero() {
+        return Err(Error::Deactivated(did.into()));
+    }
+    let controller_id = format!("{}#controller", did);
+    let mut document = Document {
+        context: "https://www.w3.org/ns/did/v1".into(),
+        id: did.into(),
+        assertion_method: Some(vec![controller_id.clone()]),
+        authentication: Some(vec![controller_id.clone()]),
+        capability_delegation: None,
+        capability_invocation: None,
+        key_agreement: None,
+        verification_method: vec![VerificationMethod {
+            id: controller_id,
+            key_type: "EcdsaSecp256k1RecoveryMethod2020".into(),
+            controller: did.into(),
+            // current owner account
+            public_key: Some(account_key(owner)),
+            private_key: None,
+        }],
+    };
+    for (_, vm, relationships) in keys {
+        for relationship in relationships {
+            let entries = match relationship {
+                Relationship::Authentication => &mut document.authentication,
+                Relationship::AssertionMethod => &mut document.assertion_method,
+                Relationship::KeyAgreement => &mut document.key_agreement,
+                Relationship::CapabilityInvocation => &mut document.capability_invocation,
+                Relationship::CapabilityDelegation => &mut document.capability_delegation,
+            };
+            entries.get_or_insert_with(Vec::new).push(vm.id.clone());
+        }
+        document.verification_method.push(vm);
+    }
+    Ok((document, services.into_iter().map(|(_, s)| s).collect()))
+}
+
+// Account address as key material of recovery method
+fn account_key(address: Address) -> KeyFormat {
+    KeyFormat::Multibase(address.as_bytes().to_vec())
+}
+
+// Decodes zero padded `bytes32` token into string
+fn bytes32_to_string(token: Token) -> String {
+    let bytes = token.into_fixed_bytes().unwrap_or_default();
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into()
+}
+
+#[cfg(test)]
+mod did_ethr_tests {
+    use super::*;
+    use crate::DdoParser;
+    use std::env;
+
+    // Resolver of local dev chain with the registry deployed, configured by
+    //  `ETHR_PROVIDER_URL` (default `http://127.0.0.1:8545`),
+    //  `ETHR_REGISTRY_ADDRESS` and `ETHR_NETWORK` (default `dev`).
+    fn dev_resolver() -> DidEthrResolver {
+        let provider = env::var("ETHR_PROVIDER_URL").unwrap_or("http://127.0.0.1:8545".into());
+        let registry = env::var("ETHR_REGISTRY_ADDRESS")
+            .expect("ETHR_REGISTRY_ADDRESS of deployed ERC-1056 registry is required");
+        let network = env::var("ETHR_NETWORK").unwrap_or("dev".into());
+        DidEthrResolver::new(&provider, &registry, &network).unwrap()
+    }
+
+    fn did(resolver: &DidEthrResolver, account: Address) -> String {
+        format!(
+            "did:ethr:{}:0x{}",
+            resolver.network,
+            hex::encode(account.as_bytes())
+        )
+    }
+
+    fn bytes32(s: &str) -> Token {
+        let mut bytes = vec![0; 32];
+        bytes[..s.len()].copy_from_slice(s.as_bytes());
+        Token::FixedBytes(bytes)
+    }
+
+    // Sends registry transaction from unlocked dev chain `account`
+    async fn send(
+        resolver: &DidEthrResolver,
+        function: &str,
+        params: Vec<Token>,
+        account: Address,
+    ) {
+        let options = Options {
+            gas: Some(U256::from(300_000)),
+            ..Options::default()
+        };
+        resolver
+            .contract
+            .call_with_confirmations(function, params.as_slice(), account, options, 0)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn identity_parsing_test() {
+        let resolver =
+            DidEthrResolver::new("http://127.0.0.1:8545", &"11".repeat(20), "dev").unwrap();
+        let address = "0xb9c5714089478a327f09197987f16f9e5d936e8a";
+        let (did, identity) = resolver
+            .identity(&format!("did:ethr:dev:{}#controller", address))
+            .unwrap();
+        assert_eq!(did, format!("did:ethr:dev:{}", address));
+        assert_eq!(identity, parse_address(address).unwrap());
+        assert!(matches!(
+            resolver.identity(&format!("did:ethr:{}", address)),
+            Err(Error::MethodNotSupported(_))
+        ));
+        assert!(matches!(
+            resolver.identity(&format!("did:ethr:dev:0x02{}", "11".repeat(32))),
+            Err(Error::MethodNotSupported(_))
+        ));
+        assert!(matches!(
+            resolver.identity("did:ethr:dev:0xb9c5714089"),
+            Err(Error::InvalidDid(_))
+        ));
+    }
+
+    #[test]
+    fn build_document_test() {
+        let identity = Address::from_low_u64_be(0x1d);
+        let (owner, delegate, expired) = (
+            Address::from_low_u64_be(0x0e),
+            Address::from_low_u64_be(0xde),
+            Address::from_low_u64_be(0xee),
+        );
+        let did = format!("did:ethr:dev:0x{}", hex::encode(identity.as_bytes()));
+        let (now, valid_to) = (U256::from(1000), U256::from(2000));
+        let history = vec![
+            RegistryEvent::Owner { owner },
+            RegistryEvent::Delegate {
+                delegate_type: "sigAuth".into(),
+                delegate,
+                valid_to,
+            },
+            RegistryEvent::Delegate {
+                delegate_type: "veriKey".into(),
+                delegate: expired,
+                valid_to: U256::from(500),
+            },
+            RegistryEvent::Attribute {
+                name: "did/pub/Ed25519/veriKey/base58".into(),
+                value: vec![1; 32],
+                valid_to,
+            },
+            RegistryEvent::Attribute {
+                name: "did/pub/X25519/enc/base64".into(),
+                value: vec![2; 32],
+                valid_to,
+            },
+            RegistryEvent::Attribute {
+                name: "did/svc/DIDCommMessaging".into(),
+                value: b"https://example.com".to_vec(),
+                valid_to,
+            },
+        ];
+        let (doc, services) = build_document(&did, identity, history, now).unwrap();
+        assert_eq!(doc.id, did);
+        assert_eq!(doc.verification_method[0].controller, did);
+        assert_eq!(
+            doc.verification_method[0].public_key,
+            Some(KeyFormat::Multibase(owner.as_bytes().to_vec()))
+        );
+        assert_eq!(
+            doc.verification_method[1].public_key,
+            Some(KeyFormat::Multibase(delegate.as_bytes().to_vec()))
+        );
+        // expired delegate still takes its number
+        let ids: Vec<&str> = doc
+            .verification_method
+            .iter()
+            .map(|vm| vm.id.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                format!("{}#controller", did),
+                format!("{}#delegate-1", did),
+                format!("{}#delegate-3", did),
+                format!("{}#delegate-4", did),
+            ]
+        );
+        assert!(doc.is_authorized_for("#delegate-1", Relationship::Authentication));
+        assert!(doc.is_authorized_for("#delegate-3", Relationship::AssertionMethod));
+        assert!(!doc.is_authorized_for("#delegate-3", Relationship::Authentication));
+        assert_eq!(doc.find_public_key_for_curve("Ed25519"), Some(vec![1; 32]));
+        assert_eq!(
+            doc.find_key_agreement("X25519KeyAgreementKey2019")
+                .and_then(|ka| ka.public_key()),
+            Some(vec![2; 32])
+        );
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].id, format!("{}#service-1", did));
+        assert_eq!(services[0].service_type, "DIDCommMessaging");
+        assert_eq!(
+            services[0].service_endpoint,
+            Value::String("https://example.com".into())
+        );
+    }
+
+    #[test]
+    fn build_document_revocation_test() {
+        let identity = Address::from_low_u64_be(0x1d);
+        let delegate = Address::from_low_u64_be(0xde);
+        let did = format!("did:ethr:dev:0x{}", hex::encode(identity.as_bytes()));
+        let now = U256::from(1000);
+        let history = vec![
+            RegistryEvent::Delegate {
+                delegate_type: "sigAuth".into(),
+                delegate,
+                valid_to: U256::from(2000),
+            },
+            RegistryEvent::Attribute {
+                name: "did/svc/DIDCommMessaging".into(),
+                value: b"https://example.com".to_vec(),
+                valid_to: U256::from(2000),
+            },
+            // revoked by validity in the past
+            RegistryEvent::Delegate {
+                delegate_type: "sigAuth".into(),
+                delegate,
+                valid_to: U256::zero(),
+            },
+            RegistryEvent::Attribute {
+                name: "did/svc/DIDCommMessaging".into(),
+                value: b"https://example.com".to_vec(),
+                valid_to: U256::zero(),
+            },
+        ];
+        let (doc, services) = build_document(&did, identity, history, now).unwrap();
+        assert_eq!(doc.verification_method.len(), 1);
+        assert_eq!(doc.verification_method[0].controller, did);
+        assert_eq!(
+            doc.verification_method[0].public_key,
+            Some(KeyFormat::Multibase(identity.as_bytes().to_vec()))
+        );
+        assert_eq!(
+            doc.authentication,
+            Some(vec![format!("{}#controller", did)])
+        );
+        assert!(services.is_empty());
+        let history = vec![RegistryEvent::Owner {
+            owner: Address::zero(),
+        }];
+        assert!(matches!(
+            build_document(&did, identity, history, now),
+            Err(Error::Deactivated(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires local dev chain with ERC-1056 registry"]
+    async fn resolve_unchanged_identity_test() {
+        let resolver = dev_resolver();
+        let did = did(&resolver, Address::from_low_u64_be(0xe7));
+        let result = resolver.resolve_with_metadata_async(&did).await;
+        let doc = result.did_document.unwrap();
+        assert_eq!(doc.verification_method.len(), 1);
+        assert_eq!(doc.verification_method[0].controller, did);
+        assert!(doc.is_authorized_for("#controller", Relationship::Authentication));
+        assert!(result.did_document_metadata.version_id.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore = "requires local dev chain with ERC-1056 registry"]
+    async fn resolve_delegates_and_attributes_test() {
+        let resolver = dev_resolver();
+        let accounts = resolver.w3.eth().accounts().await.unwrap();
+        let (identity, delegate) = (accounts[1], accounts[2]);
+        let validity = Token::Uint(U256::from(86400));
+        send(
+            &resolver,
+            "addDelegate",
+            vec![
+                Token::Address(identity),
+                bytes32("sigAuth"),
+                Token::Address(delegate),
+                validity.clone(),
+            ],
+            identity,
+        )
+        .await;
+        for (name, value) in [
+            ("did/pub/Ed25519/veriKey/base58", vec![1; 32]),
+            ("did/pub/X25519/enc/base64", vec![2; 32]),
+            ("did/svc/DIDCommMessaging", b"https://example.com".to_vec()),
+        ]
+        .iter()
+        {
+            send(
+                &resolver,
+                "setAttribute",
+                vec![
+                    Token::Address(identity),
+                    bytes32(name),
+                    Token::Bytes(value.clone()),
+                    validity.clone(),
+                ],
+                identity,
+            )
+            .await;
+        }
+        let did = did(&resolver, identity);
+        // synchronous API from within of the runtime
+        let doc = resolver.resolve(&did).unwrap();
+        assert!(doc
+            .keys_for_relationship(Relationship::Authentication)
+            .iter()
+            .any(|vm| vm.id.contains("#delegate-")
+                && vm.key_type == "EcdsaSecp256k1RecoveryMethod2020"));
+        assert_eq!(doc.find_public_key_for_curve("Ed25519"), Some(vec![1; 32]));
+        assert_eq!(
+            doc.find_key_agreement("X25519KeyAgreementKey2019")
+                .and_then(|ka| ka.public_key()),
+            Some(vec![2; 32])
+        );
+        let services = resolver.services_async(&did).await.unwrap();
+        assert!(services.iter().any(|s| s.service_type == "DIDCommMessaging"
+            && s.service_endpoint == Value::String("https://example.com".into())));
+        let result = resolver.resolve_with_metadata_async(&did).await;
+        assert!(result.did_document_metadata.version_id.is_some());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires local dev chain with ERC-1056 registry"]
+    async fn deactivated_identity_test() {
+        let resolver = dev_resolver();
+        let accounts = resolver.w3.eth().accounts().await.unwrap();
+        let identity = accounts[3];
+        let owner: Address = resolver
+            .contract
+            .query(
+                "identityOwner",
+                (Token::Address(identity),),
+                None,
+                Options::default(),
+                None,
+            )
+            .await
+            .unwrap();
+        if owner == identity {
+            send(
+                &resolver,
+                "changeOwner",
+                vec![Token::Address(identity), Token::Address(Address::zero())],
+                identity,
+            )
+            .await;
+        }
+        let result = resolver
+            .resolve_with_metadata_async(&did(&resolver, identity))
+            .await;
+        assert!(result.did_document.is_none());
+        assert_eq!(
+            result.did_resolution_metadata.error,
+            Some("deactivated".into())
+        );
+        assert_eq!(result.did_document_metadata.deactivated, Some(true));
+    }
+}
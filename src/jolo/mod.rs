@@ -1,24 +1,34 @@
-use crate::{DdoResolver, Error};
+use crate::{
+    ethereum::{block_on, connect},
+    AsyncDdoResolver, DdoResolver, DidUrl, Error, ResolutionResult,
+};
+use async_trait::async_trait;
 #[cfg(feature = "registrar")]
 use did_key::Document;
-use ipfs_api::IpfsClient;
+#[cfg(feature = "registrar")]
+use ipfs_api::{IpfsApi, IpfsClient};
 use serde::Deserialize;
-use std::fs;
 #[cfg(feature = "registrar")]
 use std::io::Cursor;
+use std::fs;
+#[cfg(test)]
+use web3::types::Address;
 #[cfg(feature = "registrar")]
 use web3::types::{H160, U256};
 use web3::{
     contract::{Contract, Options},
     ethabi::Token,
-    futures::StreamExt,
     transports::Http,
-    types::Address,
     Web3,
 };
 
-pub const RINKEBY: &'static str = "./config/jolo_rinkeby.json";
-pub const MAINNET: &'static str = "./config/jolo.json";
+pub const RINKEBY: &str = "./config/jolo_rinkeby.json";
+pub const MAINNET: &str = "./config/jolo.json";
+
+/// Environment variable with path to `JoloConfig` JSON file used by
+///     `JoloConfig::from_env()`.
+///
+pub const CONFIG_ENV: &str = "JOLO_CONFIG";
 
 #[derive(Debug, Deserialize)]
 pub struct JoloConfig {
@@ -27,9 +37,37 @@ pub struct JoloConfig {
     ipfs_endpoint: String,
 }
 
+impl JoloConfig {
+    /// Config read from file at path in `JOLO_CONFIG` environment variable.
+    /// Used to configure `JoloResolver` of `ResolverRegistry::default()`,
+    ///     which fails to resolve `did:jolo` with `Error::ConfigOpenError`
+    ///     if the variable is not set or this (or creating resolver from
+    ///     it) fails.
+    ///
+    pub fn from_env() -> Result<Self, Error> {
+        match std::env::var(CONFIG_ENV) {
+            Ok(path) => read_config(&path),
+            Err(_) => Err(Error::ConfigOpenError(format!(
+                "{} is not set, no did:jolo network is configured",
+                CONFIG_ENV
+            ))),
+        }
+    }
+
+    /// Rinkeby config bundled with the crate. Rinkeby testnet is shut
+    ///     down, so this is never used implicitly and is only kept for
+    ///     explicit opt-in, e.g. with `ResolverRegistry::with_jolo()`.
+    ///
+    pub fn bundled() -> Result<Self, Error> {
+        Ok(serde_json::from_str(include_str!(
+            "../../config/jolo_rinkeby.json"
+        ))?)
+    }
+}
+
 /// Instance of actual resolver
 /// Implements `DdoResolver` trait for synchronous resolution
-///  and `async_resolve()` method for asynchronous resolution
+///  and `AsyncDdoResolver` trait for asynchronous resolution
 /// Can (and should) be used for cached/once instantiated
 ///  resolver for smoother performance.
 /// Available ONLY with `jolo` feature
@@ -37,6 +75,9 @@ pub struct JoloConfig {
 pub struct JoloResolver {
     contract: Contract<Http>,
     _w3: Web3<Http>,
+    ipfs_endpoint: String,
+    http: reqwest::Client,
+    #[cfg(feature = "registrar")]
     client: IpfsClient,
 }
 
@@ -54,19 +95,20 @@ impl JoloResolver {
         contract_address: &str,
         ipfs_endpoint: &str,
     ) -> Result<Self, Error> {
-        let http = Http::new(provider_address)?;
-        let _w3 = Web3::new(http);
-        let c_address = Address::from_slice(&hex::decode(contract_address)?);
-        let ipfs_client: IpfsClient = ipfs_api::TryFromUri::from_str(ipfs_endpoint)
-            .map_err(|e| Error::UriParseError(e.to_string()))?;
+        let (_w3, contract) = connect(
+            provider_address,
+            contract_address,
+            include_bytes!("../resources/jolo_token.json"),
+        )?;
+        reqwest::Url::parse(ipfs_endpoint).map_err(|e| Error::UriParseError(e.to_string()))?;
         Ok(Self {
-            contract: Contract::from_json(
-                _w3.eth(),
-                c_address,
-                include_bytes!("../resources/jolo_token.json"),
-            )?,
+            contract,
             _w3,
-            client: ipfs_client,
+            ipfs_endpoint: ipfs_endpoint.trim_end_matches('/').into(),
+            http: reqwest::Client::new(),
+            #[cfg(feature = "registrar")]
+            client: ipfs_api::TryFromUri::from_str(ipfs_endpoint)
+                .map_err(|e| Error::UriParseError(e.to_string()))?,
         })
     }
 
@@ -75,7 +117,12 @@ impl JoloResolver {
     ///  with generic constructor.
     ///
     pub fn new_from_cfg(path: &str) -> Result<Self, Error> {
-        let config = read_config(path)?;
+        Self::from_config(&read_config(path)?)
+    }
+
+    /// Constructor from already loaded `JoloConfig`.
+    ///
+    pub fn from_config(config: &JoloConfig) -> Result<Self, Error> {
         Self::new(
             &config.provider_url,
             &config.contract_address,
@@ -86,20 +133,21 @@ impl JoloResolver {
     /// Resolver Ethereum record from jolocom contract
     /// #Parameters
     /// `did_url` - is DID url of identifier,
-    ///  must start with "did:jolo:"
+    ///  must start with "did:jolo:" followed by 32 hex encoded bytes
     ///  otherwise returns error: `Error::NotDidJolo`
     ///
     pub async fn resolve_record(&self, did_url: String) -> Result<String, Error> {
-        if !did_url.starts_with("did::jolo:") && did_url.len() != 73 {
+        let url: DidUrl = did_url.parse()?;
+        if url.method != "jolo" || url.method_specific_id.len() != 64 {
             return Err(Error::NotDidJolo);
         }
-        let url_token = Token::FixedBytes(hex::decode(did_url.trim_start_matches("did:jolo:"))?);
+        let url_token = Token::FixedBytes(hex::decode(&url.method_specific_id)?);
         let response: String = self
             .contract
             .query("getRecord", (url_token,), None, Options::default(), None)
             .await?;
         if response.is_empty() {
-            Err(Error::DidResolutionFailed)
+            Err(Error::NotFound(format!("no record for {}", did_url)))
         } else {
             Ok(response)
         }
@@ -110,13 +158,25 @@ impl JoloResolver {
     /// `hash` - hash returned by `resolve_record()` method;
     ///
     pub async fn get_ipfs_record(&self, hash: &str) -> Result<String, Error> {
-        let full_path = format!("api/v0/cat/{}", hash);
-        let (ddo, _) = self.client.get(&full_path).into_future().await;
-        match ddo {
-            Some(Ok(bytes)) => Ok(String::from_utf8(bytes.as_ref().to_vec())?),
-            Some(Err(e)) => Err(Error::IpfsResponseError(e.to_string())),
-            _ => Err(Error::DidResolutionFailed),
+        // plain HTTP API call, as `IpfsApi` streams are not `Send`
+        let ipfs_error = |e: reqwest::Error| Error::IpfsResponseError(e.to_string());
+        let response = self
+            .http
+            .post(format!("{}/api/v0/cat", self.ipfs_endpoint))
+            .query(&[("arg", hash)])
+            .send()
+            .await
+            .map_err(ipfs_error)?;
+        if !response.status().is_success() {
+            return Err(Error::IpfsResponseError(
+                response.text().await.map_err(ipfs_error)?,
+            ));
         }
+        let ddo = response.bytes().await.map_err(ipfs_error)?.to_vec();
+        if ddo.is_empty() {
+            return Err(Error::NotFound(format!("no IPFS record {}", hash)));
+        }
+        Ok(String::from_utf8(ddo)?)
     }
 
     #[cfg(feature = "registrar")]
@@ -132,8 +192,8 @@ impl JoloResolver {
     /// Does the same as `DdoResolver::resolve()` but asynchronously
     /// #Parameters
     /// * `did_url` - is DID url of identifier,
-    ///  must start with "did:jolo:"
-    ///  otherwise returns error: `Error::NotDidJolo`
+    ///   must start with "did:jolo:"
+    ///   otherwise returns error: `Error::NotDidJolo`
     ///
     pub async fn resolve_async(&self, did_url: &str) -> Result<did_key::Document, Error> {
         Ok(serde_json::from_str(
@@ -185,10 +245,36 @@ impl JoloResolver {
 
 impl DdoResolver for JoloResolver {
     fn resolve(&self, did_url: &str) -> Result<did_key::Document, Error> {
-        let rt = tokio::runtime::Runtime::new()?;
-        let hash = rt.block_on(self.resolve_record(did_url.into()))?;
-        let ddo_from_ipfs = rt.block_on(self.get_ipfs_record(&hash))?;
-        Ok(serde_json::from_str(&ddo_from_ipfs)?)
+        block_on(JoloResolver::resolve_async(self, did_url))?
+    }
+
+    fn resolve_with_metadata(&self, did_url: &str) -> ResolutionResult {
+        block_on(self.resolve_with_metadata_async(did_url)).unwrap_or_else(ResolutionResult::from)
+    }
+}
+
+#[async_trait]
+impl AsyncDdoResolver for JoloResolver {
+    async fn resolve_async(&self, did_url: &str) -> Result<did_key::Document, Error> {
+        JoloResolver::resolve_async(self, did_url).await
+    }
+
+    /// Reports IPFS hash of the document as `versionId`.
+    ///
+    async fn resolve_with_metadata_async(&self, did_url: &str) -> ResolutionResult {
+        let hash = match self.resolve_record(did_url.into()).await {
+            Ok(hash) => hash,
+            Err(e) => return e.into(),
+        };
+        let document = match self.get_ipfs_record(&hash).await {
+            Ok(ddo) => serde_json::from_str(&ddo).map_err(Error::from),
+            Err(e) => Err(e),
+        };
+        let mut result: ResolutionResult = document.into();
+        if result.is_ok() {
+            result.did_document_metadata.version_id = Some(hash);
+        }
+        result
     }
 }
 
@@ -204,6 +290,86 @@ fn rinkeby_config_loading() {
     assert!(cfg.is_ok());
 }
 
+#[test]
+fn bundled_config_loading() {
+    let cfg = JoloConfig::bundled().unwrap();
+    assert_eq!(
+        cfg.contract_address,
+        read_config(RINKEBY).unwrap().contract_address
+    );
+    // same as `ResolverRegistry::with_jolo()` does to register it
+    assert!(JoloResolver::from_config(&cfg).is_ok());
+}
+
+#[test]
+fn unset_env_config() {
+    if std::env::var(CONFIG_ENV).is_err() {
+        assert!(matches!(
+            JoloConfig::from_env(),
+            Err(Error::ConfigOpenError(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn reject_non_jolo_did() {
+    let resolver = JoloResolver::from_config(&JoloConfig::bundled().unwrap()).unwrap();
+    for did in [
+        "did:key:f334484858571199b681f6dfdd9ecd2f01df5b38f8379b3aaa89436c61fd1955",
+        "did:jolo:f334484858571199b681f6dfdd9ecd2f01df5b38f8379b3aaa89436c61fd19",
+    ] {
+        assert!(matches!(
+            resolver.resolve_record(did.into()).await,
+            Err(Error::NotDidJolo)
+        ));
+    }
+}
+
+// Runs on current thread runtime, as `#[tokio::test]` does by default
+#[tokio::test]
+async fn local_ipfs_record() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 1024];
+            let read = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..read]);
+            let body = if request.starts_with("POST /api/v0/cat?arg=Qmdoc ") {
+                "{\"id\": \"did:jolo:1\"}"
+            } else {
+                ""
+            };
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    let config = JoloConfig::bundled().unwrap();
+    let resolver = JoloResolver::new(
+        &config.provider_url,
+        &config.contract_address,
+        &format!("http://127.0.0.1:{}/", port),
+    )
+    .unwrap();
+    assert_eq!(
+        resolver.get_ipfs_record("Qmdoc").await.unwrap(),
+        "{\"id\": \"did:jolo:1\"}"
+    );
+    assert!(matches!(
+        resolver.get_ipfs_record("Qmnone").await,
+        Err(Error::NotFound(_))
+    ));
+}
+
 #[test]
 fn eth_address_from_str() {
     let cfg = read_config(RINKEBY).unwrap();
@@ -211,8 +377,7 @@ fn eth_address_from_str() {
     assert!(decoded.is_ok());
     let decoded_raw = decoded.unwrap();
     let address = Address::from_slice(&decoded_raw);
-    println!("{:?}", address);
-    assert!(true)
+    assert_eq!(address.as_bytes(), &decoded_raw[..]);
 }
 
 #[cfg(test)]
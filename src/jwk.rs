@@ -0,0 +1,125 @@
+use crate::{
+    public_key_bytes, AsyncDdoResolver, DdoResolver, DidUrl, Document, Error, KeyFormat,
+    VerificationMethod, JWK,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// Unit struct which has implementation of `DdoResolver` trait for
+///     `did:jwk` document resolver:
+///     https://github.com/quartzjer/did-jwk/blob/main/spec.md
+/// Document has single verification method `#0` with the JWK, which is
+///     used for encryption only (`keyAgreement`) if JWK has `"use": "enc"`
+///     or is X25519 key, and for signing only otherwise.
+/// Available ONLY with `didjwk` feature
+///
+pub struct DidJwkResolver;
+
+impl DdoResolver for DidJwkResolver {
+    fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+        let url: DidUrl = did_url.parse()?;
+        if url.method != "jwk" {
+            return Err(Error::InvalidDid(format!("not a did:jwk: {}", did_url)));
+        }
+        let did = url.did();
+        let invalid = |reason: &str| Error::InvalidDid(format!("{}: {}", reason, did));
+        let json = base64_url::decode(&url.method_specific_id)
+            .map_err(|_| invalid("method specific id is not base64url encoded"))?;
+        let value: Value = serde_json::from_slice(&json).map_err(|_| invalid("invalid JWK"))?;
+        if value.get("d").is_some() {
+            return Err(invalid("JWK must not contain private key"));
+        }
+        let jwk: JWK = serde_json::from_value(value.clone()).map_err(|_| invalid("invalid JWK"))?;
+        let public_key = KeyFormat::JWK(jwk);
+        if public_key_bytes(&public_key).is_none() {
+            return Err(invalid("unsupported or malformed JWK"));
+        }
+        let (signing, encryption) = match value.get("use").and_then(Value::as_str) {
+            Some("enc") => (false, true),
+            Some("sig") => (true, false),
+            _ if value.get("crv").and_then(Value::as_str) == Some("X25519") => (false, true),
+            _ => (true, false),
+        };
+        let id = format!("{}#0", did);
+        let references = |included: bool| {
+            if included {
+                Some(vec![id.clone()])
+            } else {
+                None
+            }
+        };
+        Ok(Document {
+            context: "https://www.w3.org/ns/did/v1".into(),
+            id: did.clone(),
+            assertion_method: references(signing),
+            authentication: references(signing),
+            capability_delegation: references(signing),
+            capability_invocation: references(signing),
+            key_agreement: references(encryption),
+            verification_method: vec![VerificationMethod {
+                id: id.clone(),
+                key_type: "JsonWebKey2020".into(),
+                controller: did.clone(),
+                public_key: Some(public_key),
+                private_key: None,
+            }],
+        })
+    }
+}
+
+#[async_trait]
+impl AsyncDdoResolver for DidJwkResolver {
+    async fn resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+        self.resolve(did_url)
+    }
+}
+
+#[cfg(test)]
+mod did_jwk_tests {
+    use super::*;
+    use crate::{get_sign_and_crypto_keys, try_resolve_any, DdoParser, Relationship};
+
+    const P256: &str = "did:jwk:eyJjcnYiOiJQLTI1NiIsImt0eSI6IkVDIiwieCI6ImFjYklRaXVNczNpOF91c3pFakoydHBUdFJNNEVVM3l6OTFQSDZDZEgyVjAiLCJ5IjoiX0tjeUxqOXZXTXB0bm1LdG00NkdxRHo4d2Y3NEk1TEtncmwyR3pIM25TRSJ9";
+    const X25519: &str = "did:jwk:eyJrdHkiOiJPS1AiLCJjcnYiOiJYMjU1MTkiLCJ1c2UiOiJlbmMiLCJ4IjoiM3A3YmZYdDl3YlRUVzJIQzdPUTFOei1EUThoYmVHZE5yZngtRkctSUswOCJ9";
+
+    #[test]
+    fn resolve_signing_key_test() {
+        let doc = try_resolve_any(P256).unwrap();
+        assert_eq!(doc.id, P256);
+        assert_eq!(doc.verification_method[0].id, format!("{}#0", P256));
+        assert!(doc.is_authorized_for("#0", Relationship::Authentication));
+        assert!(!doc.is_authorized_for("#0", Relationship::KeyAgreement));
+        let (sign, _) = get_sign_and_crypto_keys(&doc);
+        assert_eq!(sign[0].public_key.len(), 65);
+    }
+
+    #[test]
+    fn resolve_encryption_key_test() {
+        let doc = DidJwkResolver.resolve(&format!("{}#0", X25519)).unwrap();
+        assert_eq!(doc.id, X25519);
+        assert!(doc.authentication.is_none());
+        assert!(doc.assertion_method.is_none());
+        let ka = doc.find_key_agreement("#0").unwrap();
+        assert_eq!(ka.public_key().map(|k| k.len()), Some(32));
+    }
+
+    #[test]
+    fn reject_invalid_jwk_test() {
+        let encode = |json: &str| format!("did:jwk:{}", base64_url::encode(json));
+        for did in [
+            "did:jwk:a".to_string(),
+            encode("not json"),
+            encode(r#"{"kty":"RSA","n":"0vx7","e":"AQAB"}"#),
+            encode(r#"{"kty":"OKP","crv":"Ed25519","x":"AAAA"}"#),
+            encode(
+                r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo","d":"nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A"}"#,
+            ),
+        ] {
+            assert!(
+                matches!(DidJwkResolver.resolve(&did), Err(Error::InvalidDid(_))),
+                "{} should be rejected",
+                did
+            );
+        }
+    }
+}
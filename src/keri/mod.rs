@@ -0,0 +1,1731 @@
+use keri::{
+    derivation::{basic::Basic, self_addressing::SelfAddressing},
+    event::{
+        event_data::EventData,
+        sections::{
+            seal::{EventSeal, Seal},
+            threshold::SignatureThreshold,
+            KeyConfig,
+        },
+    },
+    event_message::{
+        signed_event_message::{
+            Message, SignedEventMessage, SignedNontransferableReceipt, SignedTransferableReceipt,
+        },
+        EventMessage,
+    },
+    event_parsing::{
+        message::{message as parse_message, signed_event_stream},
+        SignedEventData,
+    },
+    prefix::{AttachedSignaturePrefix, IdentifierPrefix, Prefix, SelfAddressingPrefix},
+    state::IdentifierState,
+};
+
+use crate::{
+    AsyncDdoResolver, DdoResolver, DidUrl, Document, Error, KeyFormat, ResolutionResult,
+    VerificationMethod,
+};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    sync::Mutex,
+};
+
+mod store;
+pub use store::{KelStore, MemoryKelStore, SledKelStore};
+
+/// `did:keri` resolver for identifiers with KEL in its `KelStore`.
+/// KEL stream may contain events of several identifiers, each of them
+///     is resolvable by its own `did:keri:<prefix>`.
+/// Every event of the KEL is validated: it must follow previous one,
+///     satisfy pre-rotation commitment and carry enough valid signatures
+///     of current keys to meet signing threshold `kt`. Events are
+///     validated when ingested and again every time KEL is read from
+///     the store, so tampered stores are not trusted.
+/// Delegated events (`dip`, `drt`) must be anchored by event seal in KEL
+///     of delegator, which is expected in the same stream (before them)
+///     or in the store. Verification methods of delegated identifiers
+///     are controlled by `did:keri` of the delegator, which is reported
+///     by `KeriKeyState::delegator` as well.
+/// Witness receipts (`rct`) may be given alongside the events or later.
+///     Events of identifiers with witnesses are accepted only once they
+///     are receipted by backer threshold `bt` of their witnesses, key
+///     state and documents are never reported for events which are not
+///     (nor for the ones following them).
+/// Receipts of transferable witnesses (`vrc`) are signed by keys of their
+///     own KEL, which is expected in the same stream or in the store.
+///     Their seal must refer to the latest establishment event of the
+///     witness when they are ingested (and to any of its establishment
+///     events once stored, as the witness may have rotated since).
+/// Historical documents are resolved with `versionId` query parameter,
+///     which is either sequence number of the event (decimal, same as
+///     reported `versionId`) or its self-addressing digest:
+///     `did:keri:<prefix>?versionId=1` has keys established by first
+///     rotation. `versionTime` is not supported, as events of KEL carry
+///     no timestamps.
+///
+/// # Example
+/// ```no_run
+/// use ddoresolver_rs::{keri::{DidKeriResolver, SledKelStore}, registry::ResolverRegistry};
+/// use std::sync::Arc;
+///
+/// let resolver = Arc::new(DidKeriResolver::with_store(SledKelStore::open("kels").unwrap()));
+/// let mut registry = ResolverRegistry::default();
+/// registry.register("keri", Box::new(resolver.clone()));
+/// // events received later are resolvable by registry without `?kerl=`
+/// resolver.ingest(r#"{"v":"KERI10JSON0000ed_",...}"#).unwrap();
+/// ```
+///
+pub struct DidKeriResolver {
+    store: Box<dyn KelStore>,
+    // held from reading stored KELs until new events are appended
+    ingesting: Mutex<()>,
+}
+
+impl DidKeriResolver {
+    /// Validates `kel` and builds resolver for its identifiers, which
+    ///     keeps them in `MemoryKelStore`.
+    /// Fails with `Error::InvalidKel` describing first invalid event,
+    ///     or stating that `kel` has no events at all.
+    ///
+    pub fn new(kel: &str) -> Result<Self, Error> {
+        let resolver = Self::with_store(MemoryKelStore::default());
+        if resolver.ingest(kel)?.is_empty() {
+            return Err(Error::InvalidKel("no events".into()));
+        }
+        Ok(resolver)
+    }
+
+    /// Resolver for identifiers of KELs in `store`.
+    ///
+    pub fn with_store(store: impl KelStore + 'static) -> Self {
+        DidKeriResolver {
+            store: Box::new(store),
+            ingesting: Mutex::new(()),
+        }
+    }
+
+    /// Validates events and witness receipts of `kel` against KELs in
+    ///     the store and appends them to it. Events and receipts which
+    ///     are already in the store are skipped, so the same (growing)
+    ///     KEL can be ingested repeatedly.
+    /// Nothing is appended if any of the events or receipts is invalid,
+    ///     or event conflicts with stored event of the same sequence number.
+    /// Concurrent ingestion through the same resolver is serialized, so
+    ///     the same events are never appended twice.
+    /// Returns prefixes of identifiers, which got new events or receipts.
+    ///
+    pub fn ingest(&self, kel: &str) -> Result<Vec<String>, Error> {
+        Ok(self.append_events(kel)?.into_keys().collect())
+    }
+
+    /// Same as `ingest()` for events received one by one (or in batches),
+    ///     but reports how current keys of identifiers changed, one
+    ///     `KeyChange` per identifier whose key state advanced (events
+    ///     without enough witness receipts do not advance it).
+    /// Takes `&mut self`, so changes are not interleaved with ingestion
+    ///     of other callers through the same resolver.
+    ///
+    pub fn apply_events(&mut self, stream: &str) -> Result<Vec<KeyChange>, Error> {
+        Ok(self
+            .append_events(stream)?
+            .into_iter()
+            .filter_map(|(prefix, (previous, current))| {
+                let current = current?;
+                if previous.as_ref().map(|state| state.sn) == Some(current.sn) {
+                    return None;
+                }
+                let previous_keys = previous
+                    .as_ref()
+                    .map(|state| KeriKeyState::from(state).public_keys)
+                    .unwrap_or_default();
+                let state = KeriKeyState::from(&current);
+                Some(KeyChange {
+                    prefix,
+                    previous_sn: previous.map(|state| state.sn),
+                    added: difference(&state.public_keys, &previous_keys),
+                    removed: difference(&previous_keys, &state.public_keys),
+                    state,
+                })
+            })
+            .collect())
+    }
+
+    // Validates events and receipts of `kel` against stored KELs and
+    //  appends new ones. Returns accepted states of identifiers with new
+    //  events or receipts before and after them (`None` if there was none).
+    fn append_events(&self, kel: &str) -> Result<BTreeMap<String, Advance>, Error> {
+        let (events, receipts) = parse_kel(kel.as_bytes())?;
+        let _ingesting = self
+            .ingesting
+            .lock()
+            .map_err(|_| Error::InternalError("KEL ingestion lock is poisoned".into()))?;
+        let mut kels: BTreeMap<String, Kel> = BTreeMap::new();
+        let mut previous: BTreeMap<String, Option<IdentifierState>> = BTreeMap::new();
+        let mut appended: Vec<(String, SignedEventData)> = vec![];
+        for event in events {
+            let prefix = event.event_message.event.prefix.to_str();
+            if !kels.contains_key(&prefix) {
+                kels.insert(prefix.clone(), self.history(&prefix)?);
+            }
+            let kel = &kels[&prefix];
+            let sn = event.event_message.event.sn;
+            if let Some(stored) = kel.states.iter().find(|state| state.sn == sn) {
+                if event.event_message.serialize().ok().as_ref() != Some(&stored.last) {
+                    return Err(Error::InvalidKel(format!(
+                        "event {}: conflicts with stored event of {}",
+                        sn, prefix
+                    )));
+                }
+                continue;
+            }
+            let accepted = kel.accepted().last().cloned();
+            let state = kel.states.last().cloned().unwrap_or_default();
+            let delegator = delegator_of(&state, &event);
+            if let Some(delegator) = delegator.as_ref() {
+                if !kels.contains_key(delegator) {
+                    kels.insert(delegator.clone(), self.history(delegator)?);
+                }
+            }
+            previous.entry(prefix.clone()).or_insert(accepted);
+            let next = validate_event(
+                state,
+                &event,
+                delegator.map(|delegator| kels[&delegator].accepted()),
+            )?;
+            kels.get_mut(&prefix).expect("inserted above").push(next);
+            appended.push((prefix, SignedEventData::from(&event)));
+        }
+        for receipt in receipts {
+            let prefix = receipt.body().event.prefix.to_str();
+            let keys = match receipt.validator_seal() {
+                Some(seal) => {
+                    let validator = seal.prefix.to_str();
+                    if !kels.contains_key(&validator) {
+                        kels.insert(validator.clone(), self.history(&validator)?);
+                    }
+                    Some(receipt.validator_keys(kels[&validator].accepted(), true)?)
+                }
+                None => None,
+            };
+            if !kels.contains_key(&prefix) {
+                kels.insert(prefix.clone(), self.history(&prefix)?);
+            }
+            let kel = kels.get_mut(&prefix).expect("inserted above");
+            let accepted = kel.accepted().last().cloned();
+            if kel.add_receipt(&receipt, keys.as_ref())? {
+                previous.entry(prefix.clone()).or_insert(accepted);
+                appended.push((prefix, receipt.into()));
+            }
+        }
+        for (prefix, data) in appended.iter() {
+            let data = data
+                .to_cesr()
+                .map_err(|e| Error::DidKeriError(e.to_string()))?;
+            self.store.append(prefix, &data)?;
+        }
+        Ok(previous
+            .into_iter()
+            .map(|(prefix, before)| {
+                let after = kels[&prefix].accepted().last().cloned();
+                (prefix, (before, after))
+            })
+            .collect())
+    }
+
+    /// Prefixes of all identifiers in the store.
+    ///
+    pub fn prefixes(&self) -> Result<Vec<String>, Error> {
+        self.store.prefixes()
+    }
+
+    /// Key state of identifier of `did_url` after the event of its
+    ///     `versionId`, or after the last accepted event of KEL if there
+    ///     is none.
+    /// Fails with `Error::NotFound` if KEL has no such accepted events.
+    ///
+    pub fn key_state(&self, did_url: &str) -> Result<KeriKeyState, Error> {
+        let (_, history, index) = self.state_of(did_url)?;
+        Ok(KeriKeyState::from(&history[index]))
+    }
+
+    // Stored KEL of identifier `prefix` with its witness receipts
+    //  (delegated events are validated against stored KEL of delegator)
+    fn history(&self, prefix: &str) -> Result<Kel, Error> {
+        self.delegated_history(prefix, &mut vec![])
+    }
+
+    // Same as `history()`, `delegated` are identifiers whose histories are
+    //  being read and which are (transitively) delegated or receipted by
+    //  `prefix`, so cycles of tampered stores are not followed forever
+    fn delegated_history(&self, prefix: &str, delegated: &mut Vec<String>) -> Result<Kel, Error> {
+        if delegated.iter().any(|delegated| delegated == prefix) {
+            return Err(Error::InvalidKel(format!("delegation cycle of {}", prefix)));
+        }
+        if delegated.len() >= MAX_DELEGATION_DEPTH {
+            return Err(Error::InvalidKel(format!(
+                "delegation chain of {} is too long",
+                prefix
+            )));
+        }
+        delegated.push(prefix.into());
+        let mut kel = Kel::default();
+        let mut delegators: BTreeMap<String, Kel> = BTreeMap::new();
+        let (events, receipts) = parse_kel(&self.store.kel(prefix)?)?;
+        for event in events {
+            if event.event_message.event.prefix.to_str() != prefix {
+                return Err(Error::InvalidKel(format!(
+                    "KEL of {} has events of other identifier",
+                    prefix
+                )));
+            }
+            let state = kel.states.last().cloned().unwrap_or_default();
+            let delegator = delegator_of(&state, &event);
+            if let Some(delegator) = delegator.as_ref() {
+                if !delegators.contains_key(delegator) {
+                    let history = self.delegated_history(delegator, delegated)?;
+                    delegators.insert(delegator.clone(), history);
+                }
+            }
+            kel.push(validate_event(
+                state,
+                &event,
+                delegator.map(|delegator| delegators[&delegator].accepted()),
+            )?);
+        }
+        for receipt in receipts {
+            let keys = match receipt.validator_seal() {
+                Some(seal) => {
+                    let validator = seal.prefix.to_str();
+                    if !delegators.contains_key(&validator) {
+                        let history = self.delegated_history(&validator, delegated)?;
+                        delegators.insert(validator.clone(), history);
+                    }
+                    Some(receipt.validator_keys(delegators[&validator].accepted(), false)?)
+                }
+                None => None,
+            };
+            kel.add_receipt(&receipt, keys.as_ref())?;
+        }
+        delegated.pop();
+        Ok(kel)
+    }
+
+    // DID of `did_url` along with accepted states of its identifier after
+    //  each event and index of the one `versionId` refers to (last if not
+    //  requested)
+    fn state_of(&self, did_url: &str) -> Result<(String, Vec<IdentifierState>, usize), Error> {
+        let url: DidUrl = did_url.parse()?;
+        if url.method != "keri" {
+            return Err(Error::InvalidDid(format!("not a did:keri: {}", did_url)));
+        }
+        let kel = self.history(&url.method_specific_id)?;
+        if kel.states.is_empty() {
+            return Err(Error::NotFound(format!(
+                "KEL has no events of {}, it establishes {}",
+                url.did(),
+                self.prefixes()?.join(", ")
+            )));
+        }
+        let history = kel.accepted();
+        if history.is_empty() {
+            return Err(Error::NotFound(format!(
+                "inception of {} has not enough witness receipts",
+                url.did()
+            )));
+        }
+        if url.query_param("versionTime").is_some() {
+            return Err(Error::InvalidDidUrl(
+                "versionTime is not supported by did:keri, KEL events have no timestamps".into(),
+            ));
+        }
+        let index = match url.query_param("versionId") {
+            None => history.len() - 1,
+            Some(version) => history
+                .iter()
+                .position(|state| is_version(state, version))
+                .ok_or_else(|| {
+                    Error::NotFound(format!("{} has no accepted event {}", url.did(), version))
+                })?,
+        };
+        Ok((url.did(), history.to_vec(), index))
+    }
+}
+
+// Longest chain of delegators followed while reading KEL
+const MAX_DELEGATION_DEPTH: usize = 16;
+
+// Accepted state of identifier before and after ingested events or receipts
+type Advance = (Option<IdentifierState>, Option<IdentifierState>);
+
+// Validated KEL of identifier: states after each of its events along with
+//  witnesses, which receipted the events
+#[derive(Default)]
+struct Kel {
+    states: Vec<IdentifierState>,
+    receipts: Vec<BTreeSet<String>>,
+}
+
+impl Kel {
+    fn push(&mut self, state: IdentifierState) {
+        self.states.push(state);
+        self.receipts.push(BTreeSet::new());
+    }
+
+    // States after events, which are receipted by at least `bt` of their
+    //  witnesses. Events following one which is not are not accepted
+    //  either, as witnesses only receipt first seen version of KEL.
+    fn accepted(&self) -> &[IdentifierState] {
+        let witnessed = self
+            .states
+            .iter()
+            .zip(self.receipts.iter())
+            .take_while(|(state, receipts)| receipts.len() as u64 >= state.tally)
+            .count();
+        &self.states[..witnessed]
+    }
+
+    // Records witnesses of `receipt`, which must all be current witnesses
+    //  of receipted event with valid signatures. `vrc` is verified with
+    //  `keys` of its witness. Returns `false` if all of them have receipted
+    //  the event already.
+    fn add_receipt(&mut self, receipt: &Receipt, keys: Option<&KeyConfig>) -> Result<bool, Error> {
+        let sn = receipt.body().event.sn;
+        let invalid =
+            |reason: String| Error::InvalidKel(format!("receipt of event {}: {}", sn, reason));
+        let index = self
+            .states
+            .iter()
+            .position(|state| state.sn == sn)
+            .ok_or_else(|| invalid("event is unknown".into()))?;
+        let state = &self.states[index];
+        match &receipt.body().event.event_data {
+            EventData::Rct(rct) if rct.receipted_event_digest.verify_binding(&state.last) => {}
+            _ => return Err(invalid("digest does not match event".into())),
+        }
+        let is_witness = |witness: &str| state.witnesses.iter().any(|w| w.to_str() == witness);
+        let mut witnesses = vec![];
+        match (receipt, keys) {
+            (Receipt::Witness(rct), _) => {
+                for (witness, signature) in rct.couplets.iter() {
+                    if !witness.verify(&state.last, signature).unwrap_or(false) {
+                        return Err(invalid(format!(
+                            "invalid signature of {}",
+                            witness.to_str()
+                        )));
+                    }
+                    witnesses.push(witness.to_str());
+                }
+            }
+            (Receipt::Validator(vrc), Some(keys)) => {
+                verify_signatures(keys, &state.last, &vrc.signatures).map_err(|reason| {
+                    invalid(format!(
+                        "{} of {}",
+                        reason,
+                        vrc.validator_seal.prefix.to_str()
+                    ))
+                })?;
+                witnesses.push(vrc.validator_seal.prefix.to_str());
+            }
+            (Receipt::Validator(_), None) => return Err(invalid("no keys of validator".into())),
+        }
+        let mut added = false;
+        for witness in witnesses {
+            if !is_witness(&witness) {
+                return Err(invalid(format!("{} is not a witness", witness)));
+            }
+            added |= self.receipts[index].insert(witness);
+        }
+        Ok(added)
+    }
+}
+
+// Witness receipt of event, either of non-transferable witness (`rct`)
+//  or of transferable one (`vrc`), whose keys are established by its KEL
+enum Receipt {
+    Witness(SignedNontransferableReceipt),
+    Validator(SignedTransferableReceipt),
+}
+
+impl Receipt {
+    fn body(&self) -> &EventMessage {
+        match self {
+            Receipt::Witness(rct) => &rct.body,
+            Receipt::Validator(vrc) => &vrc.body,
+        }
+    }
+
+    fn validator_seal(&self) -> Option<&EventSeal> {
+        match self {
+            Receipt::Witness(_) => None,
+            Receipt::Validator(vrc) => Some(&vrc.validator_seal),
+        }
+    }
+
+    // Keys established by event of `validator` accepted states, which seal
+    //  of `vrc` refers to. It must be the `latest` establishment event, if
+    //  receipt is not stored yet.
+    fn validator_keys(
+        &self,
+        validator: &[IdentifierState],
+        latest: bool,
+    ) -> Result<KeyConfig, Error> {
+        let seal = match self.validator_seal() {
+            Some(seal) => seal,
+            None => return Err(Error::InternalError("receipt has no validator seal".into())),
+        };
+        let invalid = |reason: String| {
+            Error::InvalidKel(format!(
+                "receipt of event {}: {} {}",
+                self.body().event.sn,
+                seal.prefix.to_str(),
+                reason
+            ))
+        };
+        let establishments: Vec<&IdentifierState> = validator
+            .iter()
+            .filter(|state| is_establishment(&state.last))
+            .collect();
+        let established = establishments
+            .iter()
+            .position(|state| state.sn == seal.sn && seal.event_digest.verify_binding(&state.last))
+            .ok_or_else(|| invalid(format!("has no establishment event {}", seal.sn)))?;
+        if latest && established + 1 != establishments.len() {
+            return Err(invalid(format!("has rotated keys after event {}", seal.sn)));
+        }
+        Ok(establishments[established].current.clone())
+    }
+}
+
+impl From<Receipt> for SignedEventData {
+    fn from(receipt: Receipt) -> Self {
+        match receipt {
+            Receipt::Witness(rct) => rct.into(),
+            Receipt::Validator(vrc) => vrc.into(),
+        }
+    }
+}
+
+// Whether serialized `event` establishes keys (inception or rotation)
+fn is_establishment(event: &[u8]) -> bool {
+    matches!(
+        parse_message(event).map(|(_, message)| message.event.event_data),
+        Ok(EventData::Icp(_))
+            | Ok(EventData::Rot(_))
+            | Ok(EventData::Dip(_))
+            | Ok(EventData::Drt(_))
+    )
+}
+
+// Whether `state` is result of event with sequence number or digest `version`
+fn is_version(state: &IdentifierState, version: &str) -> bool {
+    match version.parse::<u64>() {
+        Ok(sn) => state.sn == sn,
+        Err(_) => version
+            .parse::<SelfAddressingPrefix>()
+            .map(|digest| digest.verify_binding(&state.last))
+            .unwrap_or(false),
+    }
+}
+
+/// Key state of `did:keri` identifier, which `Document` has no place for:
+///     signing threshold `kt`, pre-rotation commitment `n`, witnesses `b`
+///     with their threshold `bt` and digest of the last event.
+/// Public keys are in the same order as verification methods of the
+///     document, so their indexes are the ones signatures are attached with.
+///
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeriKeyState {
+    pub prefix: String,
+    pub sn: u64,
+    /// Blake3-256 self-addressing digest, referenced by `p` of next event.
+    pub last_event_digest: String,
+    pub public_keys: Vec<String>,
+    pub signing_threshold: SignatureThreshold,
+    /// Digest of next keys and threshold, `None` if keys can not be rotated.
+    pub next_keys_digest: Option<String>,
+    pub witnesses: Vec<String>,
+    pub witness_threshold: u64,
+    pub delegator: Option<String>,
+}
+
+impl KeriKeyState {
+    /// Checks whether signatures of current keys at `indexes` (duplicates
+    ///     are counted once) satisfy signing threshold. Signatures
+    ///     themselves are expected to be verified by the caller.
+    ///
+    pub fn threshold_satisfied(&self, indexes: &[u16]) -> bool {
+        let indexes: BTreeSet<usize> = indexes.iter().map(|index| *index as usize).collect();
+        if indexes.iter().any(|index| *index >= self.public_keys.len()) {
+            return false;
+        }
+        let weighted = match &self.signing_threshold {
+            SignatureThreshold::Simple(threshold) => return indexes.len() as u64 >= *threshold,
+            SignatureThreshold::Weighted(weighted) => weighted.extract_threshold(),
+        };
+        // clauses (`&` separated) weight consecutive keys, each of them is
+        //  satisfied if weights (`,` separated) of signing keys add up to 1
+        let mut start = 0;
+        weighted.split('&').all(|clause| {
+            let weights: Vec<&str> = clause.split(',').collect();
+            let (mut numerator, mut denominator) = (0u128, 1u128);
+            for (index, weight) in weights.iter().enumerate() {
+                if !indexes.contains(&(start + index)) {
+                    continue;
+                }
+                let (n, d) = match parse_weight(weight) {
+                    Some(weight) => weight,
+                    None => return false,
+                };
+                numerator = numerator * d + n * denominator;
+                denominator *= d;
+                let divisor = gcd(numerator, denominator);
+                numerator /= divisor;
+                denominator /= divisor;
+            }
+            start += weights.len();
+            numerator >= denominator
+        })
+    }
+}
+
+// Parses `n/d` (or `n`) fraction of weighted threshold
+fn parse_weight(weight: &str) -> Option<(u128, u128)> {
+    let (n, d) = weight.split_once('/').unwrap_or((weight, "1"));
+    match (n.parse(), d.parse()) {
+        (Ok(n), Ok(d)) if d > 0 => Some((n, d)),
+        _ => None,
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Change of current keys of identifier made by events passed to
+///     `DidKeriResolver::apply_events()`. Keys are in qb64 form, same
+///     as `KeriKeyState::public_keys`; both `added` and `removed` are
+///     empty if events did not rotate keys (interactions, or rotation
+///     to the same keys).
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyChange {
+    pub prefix: String,
+    /// Sequence number of last event before applied ones, `None` if
+    ///     identifier was incepted by them.
+    pub previous_sn: Option<u64>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Key state after the last applied event.
+    pub state: KeriKeyState,
+}
+
+// Keys of `keys` which are not in `other`
+fn difference(keys: &[String], other: &[String]) -> Vec<String> {
+    keys.iter()
+        .filter(|key| !other.contains(key))
+        .cloned()
+        .collect()
+}
+
+impl From<&IdentifierState> for KeriKeyState {
+    fn from(state: &IdentifierState) -> Self {
+        KeriKeyState {
+            prefix: state.prefix.to_str(),
+            sn: state.sn,
+            last_event_digest: SelfAddressing::Blake3_256.derive(&state.last).to_str(),
+            public_keys: state
+                .current
+                .public_keys
+                .iter()
+                .map(Prefix::to_str)
+                .collect(),
+            signing_threshold: state.current.threshold.clone(),
+            next_keys_digest: state
+                .current
+                .threshold_key_digest
+                .as_ref()
+                .map(Prefix::to_str),
+            witnesses: state.witnesses.iter().map(Prefix::to_str).collect(),
+            witness_threshold: state.tally,
+            delegator: state.delegator.as_ref().map(Prefix::to_str),
+        }
+    }
+}
+
+impl DdoResolver for DidKeriResolver {
+    fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+        let (did, history, index) = self.state_of(did_url)?;
+        Ok(keri_document(&did, &history[index]))
+    }
+
+    /// Reports sequence number of the resolved event as `versionId` and
+    ///     of the following one, if any, as `nextVersionId`.
+    ///
+    fn resolve_with_metadata(&self, did_url: &str) -> ResolutionResult {
+        let (did, history, index) = match self.state_of(did_url) {
+            Ok(state) => state,
+            Err(e) => return e.into(),
+        };
+        let mut result = ResolutionResult::from_document(keri_document(&did, &history[index]));
+        result.did_document_metadata.version_id = Some(history[index].sn.to_string());
+        result.did_document_metadata.next_version_id =
+            history.get(index + 1).map(|state| state.sn.to_string());
+        result
+    }
+}
+
+#[async_trait]
+impl AsyncDdoResolver for DidKeriResolver {
+    async fn resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+        self.resolve(did_url)
+    }
+}
+
+/// Stateless `did:keri` resolver, which takes KEL from `kerl` query
+///     parameter (base64url encoded) of the url it resolves:
+///     `did:keri:<prefix>?kerl=<base64url(kel)>`
+/// Registered for `did:keri` in `ResolverRegistry::default()`.
+///
+pub struct KerlQueryResolver;
+
+impl KerlQueryResolver {
+    // Builds resolver for KEL of `did_url`, which then resolves `did_url`
+    //  itself, so its other query parameters (`versionId`) are honoured
+    fn resolver_for(did_url: &str) -> Result<DidKeriResolver, Error> {
+        let url: DidUrl = did_url.parse()?;
+        match url.query_param("kerl") {
+            Some(kerl) if !kerl.is_empty() => {
+                let kel = base64_url::decode(kerl).map_err(|e| {
+                    Error::InvalidDidUrl(format!("kerl is not base64url encoded: {}", e))
+                })?;
+                DidKeriResolver::new(&String::from_utf8_lossy(&kel))
+            }
+            _ => Err(Error::NotFound(format!(
+                "no kerl query parameter in {}",
+                did_url
+            ))),
+        }
+    }
+}
+
+impl DdoResolver for KerlQueryResolver {
+    fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+        Self::resolver_for(did_url)?.resolve(did_url)
+    }
+
+    fn resolve_with_metadata(&self, did_url: &str) -> ResolutionResult {
+        match Self::resolver_for(did_url) {
+            Ok(resolver) => resolver.resolve_with_metadata(did_url),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncDdoResolver for KerlQueryResolver {
+    async fn resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+        self.resolve(did_url)
+    }
+
+    async fn resolve_with_metadata_async(&self, did_url: &str) -> ResolutionResult {
+        self.resolve_with_metadata(did_url)
+    }
+}
+
+// Document with current keys of identifier `did` in `state`. Verification
+//  methods are identified by their index in the key set
+//  (`did:keri:<prefix>#0`), same as indexes of attached signatures.
+// `Document` has no `controller`, so delegator of delegated identifier,
+//  which has to approve its rotations, controls the verification methods.
+fn keri_document(did: &str, state: &IdentifierState) -> Document {
+    let controller = match &state.delegator {
+        Some(delegator) => format!("did:keri:{}", delegator.to_str()),
+        None => did.into(),
+    };
+    let mut document = Document {
+        context: "https://www.w3.org/ns/did/v1".into(),
+        id: did.into(),
+        verification_method: vec![],
+        assertion_method: None,
+        authentication: None,
+        capability_delegation: None,
+        capability_invocation: None,
+        key_agreement: None,
+    };
+    for (index, prefix) in state.current.public_keys.iter().enumerate() {
+        let id = format!("{}#{}", did, index);
+        if prefix.derivation == Basic::X25519 {
+            // https://www.w3.org/TR/did-core/#dfn-keyagreement
+            document
+                .key_agreement
+                .get_or_insert_with(Vec::new)
+                .push(id.clone());
+        } else {
+            for relationship in [&mut document.authentication, &mut document.assertion_method] {
+                relationship.get_or_insert_with(Vec::new).push(id.clone());
+            }
+        }
+        document.verification_method.push(VerificationMethod {
+            id,
+            key_type: as_string(&prefix.derivation),
+            controller: controller.clone(),
+            public_key: Some(KeyFormat::Multibase(prefix.derivative().to_vec())),
+            private_key: None,
+        });
+    }
+    document
+}
+
+// Helper method to get string representation of keri key type
+fn as_string(b: &Basic) -> String {
+    match b {
+        Basic::Ed25519NT | Basic::Ed25519 => "Ed25519VerificationKey2018".into(),
+        Basic::ECDSAsecp256k1 | Basic::ECDSAsecp256k1NT => {
+            "EcdsaSecp256k1VerificationKey2019".into()
+        }
+        Basic::X25519 => "X25519KeyAgreementKey2019".into(),
+        _ => "bad key type".into(),
+    }
+}
+
+// Parses signed events and witness receipts of `kel`, which must contain
+//  nothing else
+fn parse_kel(kel: &[u8]) -> Result<(Vec<SignedEventMessage>, Vec<Receipt>), Error> {
+    let (rest, messages) = signed_event_stream(kel)
+        .map_err(|e| Error::InvalidKel(format!("KEL is not parsable: {}", e)))?;
+    if !rest.is_empty() {
+        return Err(Error::InvalidKel(format!(
+            "KEL is not parsable after {} messages",
+            messages.len()
+        )));
+    }
+    let (mut events, mut receipts) = (vec![], vec![]);
+    for message in messages {
+        let sn = message.deserialized_event.event.sn;
+        match Message::try_from(message) {
+            Ok(Message::Event(event)) => events.push(event),
+            Ok(Message::NontransferableRct(rct)) => receipts.push(Receipt::Witness(rct)),
+            Ok(Message::TransferableRct(vrc)) => receipts.push(Receipt::Validator(vrc)),
+            Err(e) => return Err(Error::InvalidKel(format!("event {}: {}", sn, e))),
+        }
+    }
+    Ok((events, receipts))
+}
+
+// Delegator of identifier, whose `event` must be anchored in its KEL
+fn delegator_of(state: &IdentifierState, event: &SignedEventMessage) -> Option<String> {
+    match &event.event_message.event.event_data {
+        EventData::Dip(dip) => Some(dip.delegator.to_str()),
+        EventData::Drt(_) => state.delegator.as_ref().map(Prefix::to_str),
+        _ => None,
+    }
+}
+
+// Seals of (serialized) delegating event, which can anchor delegated ones
+fn anchored_seals(event: &[u8]) -> Vec<Seal> {
+    match parse_message(event).map(|(_, message)| message.event.event_data) {
+        Ok(EventData::Rot(rot)) | Ok(EventData::Drt(rot)) => rot.data,
+        Ok(EventData::Ixn(ixn)) => ixn.data,
+        _ => vec![],
+    }
+}
+
+// Applies `event` to `state` if it is valid continuation of the KEL and
+//  is signed by enough current keys (new ones for establishment events)
+//  and delegated events are anchored in KEL of their delegator, which
+//  `delegator` has states of
+fn validate_event(
+    state: IdentifierState,
+    event: &SignedEventMessage,
+    delegator: Option<&[IdentifierState]>,
+) -> Result<IdentifierState, Error> {
+    let message = &event.event_message;
+    let sn = message.event.sn;
+    let invalid = |reason: String| Error::InvalidKel(format!("event {}: {}", sn, reason));
+    // keri panics on prefix bindings it does not implement
+    match (&message.event.event_data, &message.event.prefix) {
+        (EventData::Icp(_), IdentifierPrefix::SelfSigning(_))
+        | (EventData::Dip(_), IdentifierPrefix::Basic(_))
+        | (EventData::Dip(_), IdentifierPrefix::SelfSigning(_)) => {
+            return Err(invalid("unsupported identifier prefix".into()))
+        }
+        _ => {}
+    }
+    if let EventData::Dip(_) | EventData::Drt(_) = message.event.event_data {
+        let seal = event
+            .delegator_seal
+            .as_ref()
+            .ok_or_else(|| invalid("delegated event has no delegator seal".into()))?;
+        let anchor = delegator
+            .and_then(|history| history.iter().find(|state| state.sn == seal.sn))
+            .filter(|anchor| seal.digest.verify_binding(&anchor.last))
+            .ok_or_else(|| invalid(format!("delegator has no event {}", seal.sn)))?;
+        let serialized = message.serialize().map_err(|e| invalid(e.to_string()))?;
+        if !anchored_seals(&anchor.last).iter().any(|anchored| {
+            matches!(anchored, Seal::Event(es) if es.event_digest.verify_binding(&serialized))
+        }) {
+            return Err(invalid(format!(
+                "delegator event {} does not anchor the event",
+                seal.sn
+            )));
+        }
+    }
+    let next = state.apply(message).map_err(|e| invalid(e.to_string()))?;
+    verify_signatures(
+        &next.current,
+        &message.serialize().map_err(|e| invalid(e.to_string()))?,
+        &event.signatures,
+    )
+    .map_err(invalid)?;
+    Ok(next)
+}
+
+// Checks that `signatures` of `data` are valid and satisfy threshold of
+//  `keys`, describing why they are not otherwise
+fn verify_signatures(
+    keys: &KeyConfig,
+    data: &[u8],
+    signatures: &[AttachedSignaturePrefix],
+) -> Result<(), String> {
+    // keri indexes weights by signature index, without checking either
+    if let SignatureThreshold::Weighted(weighted) = &keys.threshold {
+        let weights = weighted
+            .extract_threshold()
+            .split('&')
+            .map(|clause| clause.split(',').count())
+            .sum::<usize>();
+        if weights != keys.public_keys.len() {
+            return Err(format!(
+                "{} threshold weights for {} keys",
+                weights,
+                keys.public_keys.len()
+            ));
+        }
+    }
+    if signatures
+        .iter()
+        .any(|sig| sig.index as usize >= keys.public_keys.len())
+    {
+        return Err("signature index out of key set".into());
+    }
+    match keys.verify(data, signatures) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("invalid signature".into()),
+        Err(keri::error::Error::NotEnoughSigsError) => Err(format!(
+            "{} signatures do not satisfy threshold",
+            signatures.len()
+        )),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod did_keri_tests {
+    use super::*;
+    use crate::{
+        dereference, registry::ResolverRegistry, resolve_any, resolve_with_metadata,
+        try_resolve_any, try_resolve_any_async, DdoParser, DereferencedResource, Relationship,
+    };
+    use base64_url::encode;
+    use std::sync::Arc;
+
+    // 3 keys with threshold of 2
+    const MULTISIG_KEL: &str = r#"{"v":"KERI10JSON00014b_","i":"EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8","s":"0","t":"icp","kt":"2","k":["DSuhyBcPZEZLK-fcw5tzHn2N46wRCG_ZOoeKtWTOunRA","DVcuJOOJF1IE8svqEtrSuyQjGTd2HhfAkt9y2QkUtFJI","DT1iAhBWCkvChxNWsby2J0pJyxBIxbAtbLA0Ljx-Grh8"],"n":"E9izzBkXX76sqt0N-tfLzJeRqj0W56p4pDQ_ZqNCDpyw","bt":"0","b":[],"c":[],"a":[]}-AADAAhcaP-l0DkIKlJ87iIVcDx-m0iKPdSArEu63b-2cSEn9wXVGNpWw9nfwxodQ9G8J3q_Pm-AWfDwZGD9fobWuHBAAB6mz7zP0xFNBEBfSKG4mjpPbeOXktaIyX8mfsEa1A3Psf7eKxSrJ5Woj3iUB2AhhLg412-zkk795qxsK2xfdxBAACj5wdW-EyUJNgW0LHePQcSFNxW3ZyPregL4H2FoOrsPxLa3MZx6xYTh6i7YRMGY50ezEjV81hkI1Yce75M_bPCQ"#;
+    // inception, 3 rotations and interaction
+    const ROTATED_KEL: &str = r#"{"v":"KERI10JSON0000ed_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"0","t":"icp","kt":"1","k":["DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk"],"n":"EGofBtQtAeDMOO3AA4QM0OHxKyGQQ1l2HzBOtrKDnD-o","bt":"0","b":[],"c":[],"a":[]}-AABAAxemWo-mppcRkiGSOXpVwh8CYeTSEJ-a0HDrCkE-TKJ-_76GX-iD7s4sbZ7j5fdfvOuTNyuFw3a797gwpnJ-NAg{"v":"KERI10JSON000122_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"1","t":"rot","p":"EvZY9w3fS1h98tJeysdNQqT70XLLec4oso8kIYjfu2Ks","kt":"1","k":["DLqde_jCw-C3y0fTvXMXX5W7QB0188bMvXVkRcedgTwY"],"n":"EW5MfLjWGOUCIV1tQLKNBu_WFifVK7ksthNDoHP89oOc","bt":"0","br":[],"ba":[],"a":[]}-AABAAuQcoYU04XYzJxOPp4cxmvXbqVpGADfQWqPOzo1S6MajUl1sEWEL1Ry30jNXaV3-izvHRNROYtPm2LIuIimIFDg{"v":"KERI10JSON000122_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"2","t":"rot","p":"EOi_KYKjP4hinuTfgtoYj5QBw_Q1ZrRtWFQDp0qsNuks","kt":"1","k":["De5pKs8wiP9bplyjspW9L62PEANoad-5Kum1uAllRxPY"],"n":"ERKagV0hID1gqZceLsOV3s7MjcoRmCaps2bPBHvVQPEQ","bt":"0","br":[],"ba":[],"a":[]}-AABAAPKIYNAm6nmz4cv37nvn5XMKRVzfKkVpJwMDt2DG-DqTJRCP8ehCeyDFJTdtvdJHjKqrnxE4Lfpll3iUzuQM4Aw{"v":"KERI10JSON000122_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"3","t":"rot","p":"EVK1FbLl7yWTxOzPwk7vo_pQG5AumFoeSE51KapaEymc","kt":"1","k":["D2M5V_e23Pa0IAqqhNDKzZX0kRIMkJyW8_M-gT_Kw9sc"],"n":"EYJkIfnCYcMFVIEi-hMMIjBQfXcTqH_lGIIqMw4LaeOE","bt":"0","br":[],"ba":[],"a":[]}-AABAAsrKFTSuA6tEzqV0C7fEbeiERLdZpStZMCTvgDvzNMfa_Tn26ejFRZ_rDmovoo8xh0dH7SdMQ5B_FvwCx9E98Aw{"v":"KERI10JSON000098_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"4","t":"ixn","p":"EY7VDg-9Gixr9rgH2VyWGvnnoebgTyT9oieHZIaiv2UA","a":[]}-AABAAqHtncya5PNnwSbMRegftJc1y8E4tMZwajVVj2-FmGmp82b2A7pY1vr7cv36m7wPRV5Dusf4BRa5moMlHUpSqDA"#;
+
+    // delegator's inception and interactions anchoring delegated events
+    const DELEGATOR_KEL: &str = r#"{"v":"KERI10JSON0000ed_","i":"Eta8KLf1zrE5n-HZpgRAnDmxLASZdXEiU9u6aahqR8TI","s":"0","t":"icp","kt":"1","k":["DqI2cOZ06RwGNwCovYUWExmdKU983IasmUKMmZflvWdQ"],"n":"E7FuL3Z_KBgt_QAwuZi1lUFNC69wvyHSxnMFUsKjZHss","bt":"0","b":[],"c":[],"a":[]}-AABAAp8S6RgfLwdCEiz0jL9cXaDwTJF6MLuKyXp7EfJtrp2myOikOJVUB-w9UGZc1Y8dnURxhXPSca-ZEUAV73XOaAw{"v":"KERI10JSON000107_","i":"Eta8KLf1zrE5n-HZpgRAnDmxLASZdXEiU9u6aahqR8TI","s":"1","t":"ixn","p":"E1-QL0TCdsBTRaKoakLjFhjSlELK60Vv8WdRaG6zMnTM","a":[{"i":"E-9tsnVcfUyXVQyBPGfntoL-xexf4Cldt_EPzHis2W4U","s":"0","d":"E1x1JOub6oEQkxAxTNFu1Pma6y-lrbprNsaILHJHoPmY"}]}-AABAAROVSK0qK2gqlr_OUsnHNW_ksCyLVmRaysRne2dI5dweECGIy3_ZuFHyOofiDRt5tRE09PlS0uZdot6byFNr-AA{"v":"KERI10JSON000107_","i":"Eta8KLf1zrE5n-HZpgRAnDmxLASZdXEiU9u6aahqR8TI","s":"2","t":"ixn","p":"E3fUycq1G-P1K1pL2OhvY6ZU-9otSa3hXiCcrxuhjyII","a":[{"i":"E-9tsnVcfUyXVQyBPGfntoL-xexf4Cldt_EPzHis2W4U","s":"1","d":"EPjLBcb4pp-3PGvSi_fTvLvsqUqFoJ0CVCHvIFfu93Xc"}]}-AABAAclMVE-bkIn-wPiAqfgR384nWmslQHQvmo2o3xQvd_4Bt6bflc4BAmfBa03KgrDVqmB7qG2VXQbOHevkzOgRdDA"#;
+    // delegated inception and rotation with attached delegator seals
+    const DELEGATED_KEL: &str = r#"{"v":"KERI10JSON000121_","i":"E-9tsnVcfUyXVQyBPGfntoL-xexf4Cldt_EPzHis2W4U","s":"0","t":"dip","kt":"1","k":["DuK1x8ydpucu3480Jpd1XBfjnCwb3dZ3x5b1CJmuUphA"],"n":"EWWkjZkZDXF74O2bOQ4H5hu4nXDlKg2m4CBEBkUxibiU","bt":"0","b":[],"c":[],"a":[],"di":"Eta8KLf1zrE5n-HZpgRAnDmxLASZdXEiU9u6aahqR8TI"}-AABAA2_8Guj0Gf2JoNTq7hOs4u6eOOWhENALJWDfLxkVcS2uLh753FjtyE80lpeS3to1C9yvENyMnyN4q96ehA4exDA-GAB0AAAAAAAAAAAAAAAAAAAAAAQE3fUycq1G-P1K1pL2OhvY6ZU-9otSa3hXiCcrxuhjyII{"v":"KERI10JSON000122_","i":"E-9tsnVcfUyXVQyBPGfntoL-xexf4Cldt_EPzHis2W4U","s":"1","t":"drt","p":"E1x1JOub6oEQkxAxTNFu1Pma6y-lrbprNsaILHJHoPmY","kt":"1","k":["DTf6QZWoet154o9wvzeMuNhLQRr8JaAUeiC6wjB_4_08"],"n":"E8kyiXDfkE7idwWnAZQjHbUZMz-kd_yIMH0miptIFFPo","bt":"0","br":[],"ba":[],"a":[]}-AABAAAVUMNfOl9Fcqx-C3fAYnaxvsiJJO3zG6rP0FQ2WVp__hMEaprrQbJL6-Esnny3U5zvMOqbso17rvecTwmVIwDw-GAB0AAAAAAAAAAAAAAAAAAAAAAgEbOI0OIIFv2VV5bmeSq1pwCn-6b2k6TdWcCbJHE6Ly7o"#;
+
+    // inception with 3 witnesses and threshold of 2, interaction and
+    //  rotation which removes last witness
+    const WITNESSED_KEL: &str = r#"{"v":"KERI10JSON000179_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"0","t":"icp","kt":"1","k":["DuC-UaYWabAI_RA7pU9cWBcYXoUsObgxCTgHiLOpnosc"],"n":"Ezj7Y4DTiw4RGzSt_GF46HXsxY_KRLHW1l2TXzYiN4hs","bt":"2","b":["Bwgcte4jRF8S4-PGGklJRzVOC47RtGJQksrv_0bYnYn4","Bm4r6CGbd-IMF24EHyVn11VkcHtuqdKM96kH1UTe5Zd4","Bq0LA3cBlbL6i7kLJbskLyNyOQJyolydXDymew4komWA"],"c":[],"a":[]}-AABAAbcku7A8TWQRNxTw1qttsfTZAd1YV08Yjswi0e4RUshhrK8lAg7DBcrhqhl19D6Ndk9q4QIEMK-NSIIT4bG2OCg{"v":"KERI10JSON000098_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"1","t":"ixn","p":"E6JB9UzbY8cwd9W3ZxKN3v6VyyXNMrE_c0tkkJjXDROY","a":[]}-AABAAE2S7MXCAPz7mapglDuYem2uYCMSWRvedaoUjPRXJdZ0Ildv6EMjeCEyT5ojYps1O-rkr1is81KPlxC8ubZsjAg{"v":"KERI10JSON000150_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"2","t":"rot","p":"EhZXY4EFB852xrqSnwunnVIoh3DwY0sO4yeqHT8Zr968","kt":"1","k":["DL1DVk30uarhVeGBnzosN8lCcWpMB_yxALwgQmW6rLJA"],"n":"EpaIaP3PflvlWetzkOy2cl2avgUfqDbD8ljt1vp8RGn4","bt":"2","br":["Bq0LA3cBlbL6i7kLJbskLyNyOQJyolydXDymew4komWA"],"ba":[],"a":[]}-AABAAzgsodMl9RD2z2LVoQBRW_g4n6SxOMYQJYA3GW82LhrXKxfp6B_oIQY3SlCTRWTq3jsl2pAMxJHQJd7T3hYM4Bg"#;
+    // receipts of each witness for each event of `WITNESSED_KEL`
+    // transferable witness, which rotated its keys, and identifier with it
+    //  as the only witness along with its receipt of the inception
+    const VALIDATOR_KEL: &str = r#"{"v":"KERI10JSON0000ed_","i":"D0l2zRzepgqu1JoUlaDo5U9pf5dtOnBDs5Xfyr3iF-Co","s":"0","t":"icp","kt":"1","k":["D0l2zRzepgqu1JoUlaDo5U9pf5dtOnBDs5Xfyr3iF-Co"],"n":"EoTSLqOqHtXUgPIcQroGdPz-hqtweGki5UFWORodZMQ0","bt":"0","b":[],"c":[],"a":[]}-AABAAeUcO0Ih5WjDlFw0rnsiFYtJn9CxGxuagEhPVGDMNzWGZCjb_9SgbQYTvh8MDHqhBI2H_HKBEpvlokgJyl4lHBg{"v":"KERI10JSON000122_","i":"D0l2zRzepgqu1JoUlaDo5U9pf5dtOnBDs5Xfyr3iF-Co","s":"1","t":"rot","p":"ElpTORII6TvzgzXhC_Ki-qzi-CV1ZJYTzu8Ui5Sv414E","kt":"1","k":["DwsZ_XSeEBasXL5L9snaYI_W-EbfjfjbmwXvIJEAL-u8"],"n":"EfM4VVn6HvEuVgz8jWSjKGwCFZRpfRIwmjebOjgBGkys","bt":"0","br":[],"ba":[],"a":[]}-AABAAwt9o-GOrRV-yzN9nFCbEMhjC2rpcTF8WatvuOGoZ8toR3crBDXhDwMgaLWqBiJmQIQGbbqEXwydgRRojvM0EDw"#;
+    const VALIDATED_KEL: &str = r#"{"v":"KERI10JSON00011b_","i":"E_GxP-4GzuC0iBbjqfPk4jv_YuFtfiAtGscfX0zxmt3k","s":"0","t":"icp","kt":"1","k":["DaIlWcNsQxw_4RzLypmHbi5721UfTpkhT3HeX1mi4Jx0"],"n":"EMtcVyVdAPKCCugjGmriFQj9QXJg4rDg-e_gSUGCHllI","bt":"1","b":["D0l2zRzepgqu1JoUlaDo5U9pf5dtOnBDs5Xfyr3iF-Co"],"c":[],"a":[]}-AABAABaUjeRGSf47EGFWFZ63tJyX7W2Un7mspD0M0LPRH8aZjsjcfATl5_aJ9GTa20Ae1BwIpry-36zAQ_c3keIL7Cg"#;
+    const VALIDATOR_RECEIPT: &str = r#"{"v":"KERI10JSON000091_","i":"E_GxP-4GzuC0iBbjqfPk4jv_YuFtfiAtGscfX0zxmt3k","s":"0","t":"rct","d":"E0JwZMM1p8_Kdl8H4sO_BQegpc8OZhxbG0srOnildYl0"}-FABD0l2zRzepgqu1JoUlaDo5U9pf5dtOnBDs5Xfyr3iF-Co0AAAAAAAAAAAAAAAAAAAAAAQEcpL6jI7KhZf9RiJknwmMwxnnfahI2zcjbwFO_nUHglo-AABAAMXQGe-4uhMSI5qchFtsv1IuUbjOw8d8B3W6jB-hQsGUg6qNG2d9Jr-5nTZwMbJZbrDx6ChrDgd8IrzDSyx1tCA"#;
+    const RECEIPTS: [[&str; 3]; 3] = [
+        [
+            r#"{"v":"KERI10JSON000091_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"0","t":"rct","d":"E6JB9UzbY8cwd9W3ZxKN3v6VyyXNMrE_c0tkkJjXDROY"}-CABBwgcte4jRF8S4-PGGklJRzVOC47RtGJQksrv_0bYnYn40B-pNzYbt_6kUIPPS4-d-v33bFa9jRCAIrEfa8WnHXGuSrnGbuQwooS3ma9ktfmjtNFyWc7TBsV38KFkW_Tz6dCA"#,
+            r#"{"v":"KERI10JSON000091_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"0","t":"rct","d":"E6JB9UzbY8cwd9W3ZxKN3v6VyyXNMrE_c0tkkJjXDROY"}-CABBm4r6CGbd-IMF24EHyVn11VkcHtuqdKM96kH1UTe5Zd40BJNCc3ok_cvC0nix4kB4XvTOqOvyEUJiVlgvO203cE5VmEzxLtOkWoBEvEB-c-j6NW0AjWtgw-iTAx3_PCEipAw"#,
+            r#"{"v":"KERI10JSON000091_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"0","t":"rct","d":"E6JB9UzbY8cwd9W3ZxKN3v6VyyXNMrE_c0tkkJjXDROY"}-CABBq0LA3cBlbL6i7kLJbskLyNyOQJyolydXDymew4komWA0BC7aae_4ZFg8pggUnq27eBP0XigVih2DXhWYdxf461v0StF1B_YUN-DDmQzE_iflgsCgDPvnYtFIB0Q-ahe-XBw"#,
+        ],
+        [
+            r#"{"v":"KERI10JSON000091_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"1","t":"rct","d":"EhZXY4EFB852xrqSnwunnVIoh3DwY0sO4yeqHT8Zr968"}-CABBwgcte4jRF8S4-PGGklJRzVOC47RtGJQksrv_0bYnYn40BtIqjvdAy6tRZACi3q9qfpWTAJtoDXKkM5aJ4YEIWg1-wdNZDg7KmEm2PDMf_-Iv681Zgbt7tBLRTQWOhOBE7Aw"#,
+            r#"{"v":"KERI10JSON000091_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"1","t":"rct","d":"EhZXY4EFB852xrqSnwunnVIoh3DwY0sO4yeqHT8Zr968"}-CABBm4r6CGbd-IMF24EHyVn11VkcHtuqdKM96kH1UTe5Zd40BHUp_V7nkpXVkFOFmF2LLpdE6hSO8Zm1Knaa0qhm6oW3ZCHne7NNiCS_1zmn256qsvaAQlmy2C7gMpRWePO10AQ"#,
+            r#"{"v":"KERI10JSON000091_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"1","t":"rct","d":"EhZXY4EFB852xrqSnwunnVIoh3DwY0sO4yeqHT8Zr968"}-CABBq0LA3cBlbL6i7kLJbskLyNyOQJyolydXDymew4komWA0BW5Vr8HJJlXYlGk4MZ1uLp9GF1J0COvFVftpct5RRxFUFDg7x41_Z37tBYNBj8b5zj98miswr053yai2PNGRiDg"#,
+        ],
+        [
+            r#"{"v":"KERI10JSON000091_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"2","t":"rct","d":"EVixBjcNP75yPRe3rZd04E2BPShiyfyNJr3gyIYtloIM"}-CABBwgcte4jRF8S4-PGGklJRzVOC47RtGJQksrv_0bYnYn40BGuc6VoQPGdZ7qm0dTdxciZUdXBso1NhkGndXij-3w0vSQ97rmQ8NL5zVN2kpkuT0Flm-l-QHKDHqyBHbCcA9Bg"#,
+            r#"{"v":"KERI10JSON000091_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"2","t":"rct","d":"EVixBjcNP75yPRe3rZd04E2BPShiyfyNJr3gyIYtloIM"}-CABBm4r6CGbd-IMF24EHyVn11VkcHtuqdKM96kH1UTe5Zd40BCixAtbPgR2-sDWTI49RQd2RQW9Pwsum7bRMrN3xptjKPiD7LfJgCk786RxjyK_nAEGBMx7lN0ahT7eUDZKO5Ag"#,
+            r#"{"v":"KERI10JSON000091_","i":"EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw","s":"2","t":"rct","d":"EVixBjcNP75yPRe3rZd04E2BPShiyfyNJr3gyIYtloIM"}-CABBq0LA3cBlbL6i7kLJbskLyNyOQJyolydXDymew4komWA0BNxGs5_Y5ZkqMMLQpuxID4twexZkdnHnCTsJ5IULpMLpV9J6Nuea680BRRxQOHEx82_VazprlPyj0n9sQeqACAQ"#,
+        ],
+    ];
+
+    #[test]
+    fn public_key_by_type_search_ed25519_test() {
+        let kerl_str = br#"{"v":"KERI10JSON00014b_","i":"EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8","s":"0","t":"icp","kt":"2","k":["DSuhyBcPZEZLK-fcw5tzHn2N46wRCG_ZOoeKtWTOunRA","DVcuJOOJF1IE8svqEtrSuyQjGTd2HhfAkt9y2QkUtFJI","DT1iAhBWCkvChxNWsby2J0pJyxBIxbAtbLA0Ljx-Grh8"],"n":"E9izzBkXX76sqt0N-tfLzJeRqj0W56p4pDQ_ZqNCDpyw","bt":"0","b":[],"c":[],"a":[]}-AADAAhcaP-l0DkIKlJ87iIVcDx-m0iKPdSArEu63b-2cSEn9wXVGNpWw9nfwxodQ9G8J3q_Pm-AWfDwZGD9fobWuHBAAB6mz7zP0xFNBEBfSKG4mjpPbeOXktaIyX8mfsEa1A3Psf7eKxSrJ5Woj3iUB2AhhLg412-zkk795qxsK2xfdxBAACj5wdW-EyUJNgW0LHePQcSFNxW3ZyPregL4H2FoOrsPxLa3MZx6xYTh6i7YRMGY50ezEjV81hkI1Yce75M_bPCQ"#;
+        let dkr = DidKeriResolver::new(&String::from_utf8_lossy(kerl_str)).unwrap();
+        let d = dkr.resolve("did:keri:EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8");
+        assert!(d.is_ok());
+        let d = d.unwrap();
+        let k = d.find_public_key_for_curve("Ed25519");
+        assert!(k.is_some());
+    }
+
+    #[test]
+    fn public_key_by_type_search_x25519_test() {
+        let kerl_str = r#"{"v":"KERI10JSON00011c_","i":"ENRHENIVTtS1VmS1_a04BDgdsmCf1aff1-tZvfT_f4sU","s":"0","t":"icp","kt":"1","k":["DMXkLnbZZ2g_oWGzaVz7LLmqtLpI72Y4GYsBsgJfBjF4","Cz-LsoY7B6foopEV_4Cpj0ubK3VIlJ_dELmjlwmirDuU"],"n":"EiZOdQzNE8-jGNfeAFAhb7T39eyxFy0lNXE-wYzAAVLA","bt":"0","b":[],"c":[],"a":[]}-AABAA9-soOfrjhPJE4bzlzhqSYKOIAAfTPzDM7ZNskZQ323IktarZYpc1NU178tAIYFErpDt6hoDbeE9dBsDXd3BJCw"#;
+        let dkr = DidKeriResolver::new(kerl_str).unwrap();
+        let d = dkr.resolve("did:keri:ENRHENIVTtS1VmS1_a04BDgdsmCf1aff1-tZvfT_f4sU");
+        assert!(d.is_ok());
+        let d = d.unwrap();
+        let k = d.find_public_key_for_curve("X25519");
+        assert!(k.is_some());
+    }
+
+    #[test]
+    fn resolve_any_keri_test() {
+        let kerl_str = r#"{"v":"KERI10JSON00014b_","i":"EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8","s":"0","t":"icp","kt":"2","k":["DSuhyBcPZEZLK-fcw5tzHn2N46wRCG_ZOoeKtWTOunRA","DVcuJOOJF1IE8svqEtrSuyQjGTd2HhfAkt9y2QkUtFJI","DT1iAhBWCkvChxNWsby2J0pJyxBIxbAtbLA0Ljx-Grh8"],"n":"E9izzBkXX76sqt0N-tfLzJeRqj0W56p4pDQ_ZqNCDpyw","bt":"0","b":[],"c":[],"a":[]}-AADAAhcaP-l0DkIKlJ87iIVcDx-m0iKPdSArEu63b-2cSEn9wXVGNpWw9nfwxodQ9G8J3q_Pm-AWfDwZGD9fobWuHBAAB6mz7zP0xFNBEBfSKG4mjpPbeOXktaIyX8mfsEa1A3Psf7eKxSrJ5Woj3iUB2AhhLg412-zkk795qxsK2xfdxBAACj5wdW-EyUJNgW0LHePQcSFNxW3ZyPregL4H2FoOrsPxLa3MZx6xYTh6i7YRMGY50ezEjV81hkI1Yce75M_bPCQ"#;
+        let full_kerl_with_url = format!(
+            "did:keri:EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8?kerl={}",
+            encode(kerl_str)
+        );
+        let res = resolve_any(&full_kerl_with_url);
+        assert!(res.is_some());
+        let doc = res.unwrap();
+        let key = doc.find_public_key_for_curve("Ed25519");
+        assert!(key.is_some());
+    }
+
+    #[test]
+    fn try_resolve_any_keri_test() {
+        let kerl_str = r#"{"v":"KERI10JSON0000ed_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"0","t":"icp","kt":"1","k":["DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk"],"n":"EGofBtQtAeDMOO3AA4QM0OHxKyGQQ1l2HzBOtrKDnD-o","bt":"0","b":[],"c":[],"a":[]}-AABAAxemWo-mppcRkiGSOXpVwh8CYeTSEJ-a0HDrCkE-TKJ-_76GX-iD7s4sbZ7j5fdfvOuTNyuFw3a797gwpnJ-NAg{"v":"KERI10JSON000122_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"1","t":"rot","p":"EvZY9w3fS1h98tJeysdNQqT70XLLec4oso8kIYjfu2Ks","kt":"1","k":["DLqde_jCw-C3y0fTvXMXX5W7QB0188bMvXVkRcedgTwY"],"n":"EW5MfLjWGOUCIV1tQLKNBu_WFifVK7ksthNDoHP89oOc","bt":"0","br":[],"ba":[],"a":[]}-AABAAuQcoYU04XYzJxOPp4cxmvXbqVpGADfQWqPOzo1S6MajUl1sEWEL1Ry30jNXaV3-izvHRNROYtPm2LIuIimIFDg{"v":"KERI10JSON000122_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"2","t":"rot","p":"EOi_KYKjP4hinuTfgtoYj5QBw_Q1ZrRtWFQDp0qsNuks","kt":"1","k":["De5pKs8wiP9bplyjspW9L62PEANoad-5Kum1uAllRxPY"],"n":"ERKagV0hID1gqZceLsOV3s7MjcoRmCaps2bPBHvVQPEQ","bt":"0","br":[],"ba":[],"a":[]}-AABAAPKIYNAm6nmz4cv37nvn5XMKRVzfKkVpJwMDt2DG-DqTJRCP8ehCeyDFJTdtvdJHjKqrnxE4Lfpll3iUzuQM4Aw{"v":"KERI10JSON000122_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"3","t":"rot","p":"EVK1FbLl7yWTxOzPwk7vo_pQG5AumFoeSE51KapaEymc","kt":"1","k":["D2M5V_e23Pa0IAqqhNDKzZX0kRIMkJyW8_M-gT_Kw9sc"],"n":"EYJkIfnCYcMFVIEi-hMMIjBQfXcTqH_lGIIqMw4LaeOE","bt":"0","br":[],"ba":[],"a":[]}-AABAAsrKFTSuA6tEzqV0C7fEbeiERLdZpStZMCTvgDvzNMfa_Tn26ejFRZ_rDmovoo8xh0dH7SdMQ5B_FvwCx9E98Aw{"v":"KERI10JSON000098_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"4","t":"ixn","p":"EY7VDg-9Gixr9rgH2VyWGvnnoebgTyT9oieHZIaiv2UA","a":[]}-AABAAqHtncya5PNnwSbMRegftJc1y8E4tMZwajVVj2-FmGmp82b2A7pY1vr7cv36m7wPRV5Dusf4BRa5moMlHUpSqDA"#;
+        let full_kerl_with_url = format!(
+            "did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk?kerl={}",
+            encode(kerl_str)
+        );
+        let res = try_resolve_any(&full_kerl_with_url);
+        assert!(res.is_ok());
+        let doc = res.unwrap();
+        let key = doc.find_public_key_for_curve("Ed25519");
+        assert!(key.is_some());
+    }
+
+    #[tokio::test]
+    async fn try_resolve_any_async_keri_test() {
+        let kerl_str = r#"{"v":"KERI10JSON0000ed_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"0","t":"icp","kt":"1","k":["DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk"],"n":"EGofBtQtAeDMOO3AA4QM0OHxKyGQQ1l2HzBOtrKDnD-o","bt":"0","b":[],"c":[],"a":[]}-AABAAxemWo-mppcRkiGSOXpVwh8CYeTSEJ-a0HDrCkE-TKJ-_76GX-iD7s4sbZ7j5fdfvOuTNyuFw3a797gwpnJ-NAg"#;
+        let full_kerl_with_url = format!(
+            "did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk?kerl={}",
+            encode(kerl_str)
+        );
+        let res = try_resolve_any_async(&full_kerl_with_url).await;
+        assert!(res.is_ok());
+        let doc = res.unwrap();
+        let key = doc.find_public_key_for_curve("Ed25519");
+        assert!(key.is_some());
+    }
+
+    #[test]
+    fn resolve_with_metadata_keri_test() {
+        let kerl_str = r#"{"v":"KERI10JSON0000ed_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"0","t":"icp","kt":"1","k":["DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk"],"n":"EGofBtQtAeDMOO3AA4QM0OHxKyGQQ1l2HzBOtrKDnD-o","bt":"0","b":[],"c":[],"a":[]}-AABAAxemWo-mppcRkiGSOXpVwh8CYeTSEJ-a0HDrCkE-TKJ-_76GX-iD7s4sbZ7j5fdfvOuTNyuFw3a797gwpnJ-NAg{"v":"KERI10JSON000122_","i":"DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk","s":"1","t":"rot","p":"EvZY9w3fS1h98tJeysdNQqT70XLLec4oso8kIYjfu2Ks","kt":"1","k":["DLqde_jCw-C3y0fTvXMXX5W7QB0188bMvXVkRcedgTwY"],"n":"EW5MfLjWGOUCIV1tQLKNBu_WFifVK7ksthNDoHP89oOc","bt":"0","br":[],"ba":[],"a":[]}-AABAAuQcoYU04XYzJxOPp4cxmvXbqVpGADfQWqPOzo1S6MajUl1sEWEL1Ry30jNXaV3-izvHRNROYtPm2LIuIimIFDg"#;
+        let full_kerl_with_url = format!(
+            "did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk?kerl={}",
+            encode(kerl_str)
+        );
+        let res = resolve_with_metadata(&full_kerl_with_url);
+        assert!(res.is_ok());
+        assert_eq!(res.did_document_metadata.version_id, Some("1".into()));
+        assert_eq!(
+            res.did_resolution_metadata.content_type,
+            Some(crate::resolution::DID_LD_JSON.into())
+        );
+    }
+
+    #[test]
+    fn reject_tampered_signature_test() {
+        let tampered = ROTATED_KEL.replace("AAqHtncya5PNnwSbMRe", "AAqHtncya5PNnwSbMRf");
+        assert!(matches!(
+            DidKeriResolver::new(&tampered),
+            Err(Error::InvalidKel(_))
+        ));
+    }
+
+    #[test]
+    fn reject_out_of_order_events_test() {
+        let events = split_events(ROTATED_KEL);
+        assert_eq!(events.len(), 5);
+        // first rotation is missing
+        let gap = [events[0].as_str(), &events[2]].concat();
+        assert!(matches!(
+            DidKeriResolver::new(&gap),
+            Err(Error::InvalidKel(_))
+        ));
+        // rotations swapped
+        let swapped = [events[0].as_str(), &events[2], &events[1]].concat();
+        assert!(DidKeriResolver::new(&swapped).is_err());
+        assert!(DidKeriResolver::new(&events[..2].concat()).is_ok());
+    }
+
+    #[test]
+    fn reject_insufficient_signatures_test() {
+        let (event, signatures) = MULTISIG_KEL.split_at(MULTISIG_KEL.find("-AAD").unwrap());
+        let single = format!("{}-AAB{}", event, &signatures[4..92]);
+        match DidKeriResolver::new(&single) {
+            Err(Error::InvalidKel(reason)) => assert!(reason.contains("threshold")),
+            _ => panic!("KEL with 1 of 2 required signatures must be rejected"),
+        }
+        assert!(DidKeriResolver::new(MULTISIG_KEL).is_ok());
+    }
+
+    #[test]
+    fn reject_weights_not_matching_keys_test() {
+        let icp = r#"{"v":"KERI10JSON00011e_","i":"EjLIO3jczUoSv86NdgZ0iMtSw9aOEEG-XkF7hi2SJWOM","s":"0","t":"icp","kt":["1"],"k":["DSuhyBcPZEZLK-fcw5tzHn2N46wRCG_ZOoeKtWTOunRA","DVcuJOOJF1IE8svqEtrSuyQjGTd2HhfAkt9y2QkUtFJI"],"n":"ExRGiGUmBflp9PDzuUiKrboz5W24803QnA8mxYyOYsSU","bt":"0","b":[],"c":[],"a":[]}"#;
+        let kel = format!("{}-AABAB{}", icp, "A".repeat(86));
+        match DidKeriResolver::new(&kel) {
+            Err(Error::InvalidKel(reason)) => assert!(reason.contains("1 threshold weights")),
+            _ => panic!("KEL with fewer weights than keys must be rejected"),
+        }
+        let did_url = format!(
+            "did:keri:EjLIO3jczUoSv86NdgZ0iMtSw9aOEEG-XkF7hi2SJWOM?kerl={}",
+            encode(&kel)
+        );
+        assert!(try_resolve_any(&did_url).is_err());
+    }
+
+    #[test]
+    fn reject_kel_of_other_identifier_test() {
+        let resolver = DidKeriResolver::new(ROTATED_KEL).unwrap();
+        assert!(resolver
+            .resolve("did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk#0")
+            .is_ok());
+        assert!(matches!(
+            resolver.resolve("did:keri:EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8"),
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            DidKeriResolver::new(""),
+            Err(Error::InvalidKel(_))
+        ));
+        assert!(matches!(
+            DidKeriResolver::new("not a KEL"),
+            Err(Error::InvalidKel(_))
+        ));
+        let e = DidKeriResolver::new("not a KEL").err().unwrap();
+        assert_eq!(e.code(), "invalidDid");
+    }
+
+    #[test]
+    fn resolve_identifiers_of_shared_stream_test() {
+        let resolver = DidKeriResolver::new(&[MULTISIG_KEL, ROTATED_KEL].concat()).unwrap();
+        assert_eq!(
+            resolver.prefixes().unwrap(),
+            vec![
+                "DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk",
+                "EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8"
+            ]
+        );
+        let multisig = resolver
+            .resolve("did:keri:EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8")
+            .unwrap();
+        assert_eq!(multisig.verification_method.len(), 3);
+        let rotated =
+            resolver.resolve_with_metadata("did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk");
+        assert_eq!(rotated.did_document.unwrap().verification_method.len(), 1);
+        assert_eq!(rotated.did_document_metadata.version_id, Some("4".into()));
+        let missing =
+            resolver.resolve_with_metadata("did:keri:ENRHENIVTtS1VmS1_a04BDgdsmCf1aff1-tZvfT_f4sU");
+        assert_eq!(
+            missing.did_resolution_metadata.error,
+            Some("notFound".into())
+        );
+        // events of the identifiers interleaved
+        let second = ROTATED_KEL[1..].find(r#"{"v""#).unwrap() + 1;
+        let (icp, rest) = ROTATED_KEL.split_at(second);
+        let interleaved = [icp, MULTISIG_KEL, rest].concat();
+        assert_eq!(
+            DidKeriResolver::new(&interleaved)
+                .unwrap()
+                .prefixes()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn unique_verification_method_ids_test() {
+        let did = "did:keri:EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8";
+        let doc = DidKeriResolver::new(MULTISIG_KEL)
+            .unwrap()
+            .resolve(did)
+            .unwrap();
+        let ids: Vec<&str> = doc
+            .verification_method
+            .iter()
+            .map(|vm| vm.id.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                format!("{}#0", did),
+                format!("{}#1", did),
+                format!("{}#2", did)
+            ]
+        );
+        let authentication = doc.keys_for_relationship(Relationship::Authentication);
+        assert_eq!(authentication.len(), 3);
+        assert!(doc.is_authorized_for("#2", Relationship::AssertionMethod));
+        assert!(doc.key_agreement.is_none());
+        let url = format!("{}?kerl={}#1", did, encode(MULTISIG_KEL));
+        match dereference(&url).content_stream {
+            Some(DereferencedResource::VerificationMethod(vm)) => {
+                assert_eq!(vm.id, format!("{}#1", did))
+            }
+            other => panic!("{:?} is not second key", other),
+        }
+    }
+
+    #[test]
+    fn multisig_key_state_test() {
+        let dkr = DidKeriResolver::new(MULTISIG_KEL).unwrap();
+        let state = dkr
+            .key_state("did:keri:EsiHneigxgDopAidk_dmHuiUJR3kAaeqpgOAj9ZZd4q8#1")
+            .unwrap();
+        assert_eq!(state.sn, 0);
+        assert_eq!(state.public_keys.len(), 3);
+        assert_eq!(state.signing_threshold, SignatureThreshold::Simple(2));
+        assert_eq!(
+            state.next_keys_digest,
+            Some("E9izzBkXX76sqt0N-tfLzJeRqj0W56p4pDQ_ZqNCDpyw".into())
+        );
+        assert!(state.witnesses.is_empty());
+        assert_eq!(state.witness_threshold, 0);
+        assert_eq!(state.delegator, None);
+        assert!(!state.threshold_satisfied(&[1]));
+        assert!(!state.threshold_satisfied(&[1, 1]));
+        assert!(!state.threshold_satisfied(&[1, 3]));
+        assert!(state.threshold_satisfied(&[0, 2]));
+        let mut weighted = state.clone();
+        weighted.signing_threshold =
+            SignatureThreshold::multi_weighted(vec![vec![(1, 1)], vec![(1, 3), (1, 3), (1, 3)]]);
+        weighted.public_keys.push("Dkey3".into());
+        assert!(weighted.threshold_satisfied(&[0, 1, 2, 3]));
+        assert!(!weighted.threshold_satisfied(&[0, 1, 2]));
+        assert!(!weighted.threshold_satisfied(&[1, 2, 3]));
+        assert!(!weighted.threshold_satisfied(&[0, 1, 2, 4]));
+        weighted.signing_threshold = SignatureThreshold::single_weighted(vec![(1, 2); 4]);
+        assert!(weighted.threshold_satisfied(&[1, 3]));
+        assert!(!weighted.threshold_satisfied(&[2, 2]));
+        let json = serde_json::to_value(&state).unwrap();
+        assert_eq!(json["signingThreshold"], "2");
+        assert_eq!(
+            json["nextKeysDigest"],
+            "E9izzBkXX76sqt0N-tfLzJeRqj0W56p4pDQ_ZqNCDpyw"
+        );
+        assert!(matches!(
+            dkr.key_state("did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk"),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn rotated_key_state_test() {
+        let did = "did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk";
+        let state = DidKeriResolver::new(ROTATED_KEL)
+            .unwrap()
+            .key_state(did)
+            .unwrap();
+        assert_eq!(state.sn, 4);
+        assert_eq!(
+            state.public_keys,
+            vec!["D2M5V_e23Pa0IAqqhNDKzZX0kRIMkJyW8_M-gT_Kw9sc".to_string()]
+        );
+        assert_eq!(
+            state.next_keys_digest,
+            Some("EYJkIfnCYcMFVIEi-hMMIjBQfXcTqH_lGIIqMw4LaeOE".into())
+        );
+        // digest of the last rotation is referenced by following interaction
+        let ixn = ROTATED_KEL.rfind(r#"{"v""#).unwrap();
+        let state = DidKeriResolver::new(&ROTATED_KEL[..ixn])
+            .unwrap()
+            .key_state(did)
+            .unwrap();
+        assert_eq!(state.sn, 3);
+        assert_eq!(
+            state.last_event_digest,
+            "EY7VDg-9Gixr9rgH2VyWGvnnoebgTyT9oieHZIaiv2UA"
+        );
+    }
+
+    #[test]
+    fn resolve_historical_version_test() {
+        let did = "did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk";
+        let dkr = DidKeriResolver::new(ROTATED_KEL).unwrap();
+        let key_of = |did_url: &str| {
+            dkr.resolve(did_url)
+                .unwrap()
+                .find_public_key_for_curve("Ed25519")
+                .unwrap()
+        };
+        let inception = key_of(&format!("{}?versionId=0", did));
+        let first_rotation = key_of(&format!("{}?versionId=1", did));
+        assert_ne!(inception, first_rotation);
+        assert_eq!(
+            key_of(&format!(
+                "{}?versionId=EvZY9w3fS1h98tJeysdNQqT70XLLec4oso8kIYjfu2Ks",
+                did
+            )),
+            inception
+        );
+        assert_eq!(key_of(&format!("{}?versionId=4", did)), key_of(did));
+        let state = dkr.key_state(&format!("{}?versionId=2#0", did)).unwrap();
+        assert_eq!(state.sn, 2);
+        assert_eq!(
+            state.public_keys,
+            vec!["De5pKs8wiP9bplyjspW9L62PEANoad-5Kum1uAllRxPY".to_string()]
+        );
+
+        let res = dkr.resolve_with_metadata(&format!("{}?versionId=1", did));
+        assert_eq!(res.did_document_metadata.version_id, Some("1".into()));
+        assert_eq!(res.did_document_metadata.next_version_id, Some("2".into()));
+        let res = dkr.resolve_with_metadata(did);
+        assert_eq!(res.did_document_metadata.next_version_id, None);
+
+        let res = dkr.resolve_with_metadata(&format!(
+            "{}?versionId=EY7VDg-9Gixr9rgH2VyWGvnnoebgTyT9oieHZIaiv2UA",
+            did
+        ));
+        assert_eq!(res.did_document_metadata.version_id, Some("3".into()));
+
+        for version in ["5", "EY7VDg-9Gixr9rgH2VyWGvnnoebgTyT9oieHZIaiv2UB", "x"] {
+            assert!(matches!(
+                dkr.resolve(&format!("{}?versionId={}", did, version)),
+                Err(Error::NotFound(_))
+            ));
+        }
+        assert!(matches!(
+            dkr.resolve(&format!("{}?versionTime=2021-05-10T17:00:00Z", did)),
+            Err(Error::InvalidDidUrl(_))
+        ));
+        assert!(matches!(
+            dkr.resolve("did:key:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk"),
+            Err(Error::InvalidDid(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_historical_version_with_kerl_test() {
+        let did = "did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk";
+        let url = format!("{}?versionId=0&kerl={}#0", did, encode(ROTATED_KEL));
+        let doc = try_resolve_any(&url).unwrap();
+        assert_eq!(doc.id, did);
+        assert_eq!(
+            doc.find_public_key_for_curve("Ed25519"),
+            DidKeriResolver::new(ROTATED_KEL)
+                .unwrap()
+                .resolve(&format!("{}?versionId=0", did))
+                .unwrap()
+                .find_public_key_for_curve("Ed25519")
+        );
+        let res = resolve_with_metadata(&url);
+        assert_eq!(res.did_document_metadata.version_id, Some("0".into()));
+        assert_eq!(res.did_document_metadata.next_version_id, Some("1".into()));
+    }
+
+    fn split_events(kel: &str) -> Vec<String> {
+        kel.split(r#"{"v""#)
+            .skip(1)
+            .map(|event| format!(r#"{{"v"{}"#, event))
+            .collect()
+    }
+
+    #[test]
+    fn ingest_into_store_test() {
+        let did = "did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk";
+        let events = split_events(ROTATED_KEL);
+        let dir = tempfile::tempdir().unwrap();
+        let resolver = DidKeriResolver::with_store(SledKelStore::open(dir.path()).unwrap());
+        assert!(matches!(resolver.resolve(did), Err(Error::NotFound(_))));
+        assert_eq!(
+            resolver.ingest(&events[..2].concat()).unwrap(),
+            vec!["DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk"]
+        );
+        assert_eq!(resolver.key_state(did).unwrap().sn, 1);
+        // already stored events are skipped
+        assert_eq!(resolver.ingest(&events[..3].concat()).unwrap().len(), 1);
+        assert!(resolver.ingest(&events[..3].concat()).unwrap().is_empty());
+        assert_eq!(resolver.key_state(did).unwrap().sn, 2);
+        // nothing is stored from stream with invalid event
+        let tampered = events[4].replace("AAqHtncya5PNnwSbMRe", "AAqHtncya5PNnwSbMRf");
+        assert!(resolver
+            .ingest(&[events[3].as_str(), &tampered].concat())
+            .is_err());
+        assert_eq!(resolver.key_state(did).unwrap().sn, 2);
+        // stored event can not be replaced
+        let conflicting = events[1].replace("DLqde_jCw", "DLqde_jCx");
+        assert!(matches!(
+            resolver.ingest(&conflicting),
+            Err(Error::InvalidKel(_))
+        ));
+        drop(resolver);
+
+        let resolver = Arc::new(DidKeriResolver::with_store(
+            SledKelStore::open(dir.path()).unwrap(),
+        ));
+        assert_eq!(resolver.key_state(did).unwrap().sn, 2);
+        let mut registry = ResolverRegistry::new();
+        registry.register("keri", Box::new(resolver.clone()));
+        resolver.ingest(&events[3..].concat()).unwrap();
+        let res = registry.resolve_with_metadata(&format!("{}#0", did));
+        assert!(res.is_ok());
+        assert_eq!(res.did_document_metadata.version_id, Some("4".into()));
+    }
+
+    #[test]
+    fn concurrent_ingest_test() {
+        let did = "did:keri:DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk";
+        let resolver = Arc::new(DidKeriResolver::with_store(MemoryKelStore::default()));
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let resolver = resolver.clone();
+                std::thread::spawn(move || resolver.ingest(ROTATED_KEL).unwrap())
+            })
+            .collect();
+        let ingested: usize = threads.into_iter().map(|t| t.join().unwrap().len()).sum();
+        // events are stored by exactly one of the threads
+        assert_eq!(ingested, 1);
+        assert_eq!(resolver.key_state(did).unwrap().sn, 4);
+        assert_eq!(
+            resolver
+                .store
+                .kel("DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk")
+                .unwrap(),
+            ROTATED_KEL.as_bytes()
+        );
+    }
+
+    #[test]
+    fn apply_events_test() {
+        let prefix = "DoQy7bwiYr80qXoISsMdGvfXmCCpZ9PUqetbR8e-fyTk";
+        let events = split_events(ROTATED_KEL);
+        let mut resolver = DidKeriResolver::new(&events[0]).unwrap();
+        let changes = resolver.apply_events(&events[1]).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].prefix, prefix);
+        assert_eq!(changes[0].previous_sn, Some(0));
+        assert_eq!(changes[0].state.sn, 1);
+        assert_eq!(changes[0].removed, vec![prefix.to_string()]);
+        assert_eq!(
+            changes[0].added,
+            vec!["DLqde_jCw-C3y0fTvXMXX5W7QB0188bMvXVkRcedgTwY".to_string()]
+        );
+        // interaction does not change keys
+        let changes = resolver.apply_events(&events[2..].concat()).unwrap();
+        assert_eq!(changes[0].previous_sn, Some(1));
+        assert_eq!(changes[0].state.sn, 4);
+        assert_eq!(
+            changes[0].removed,
+            vec!["DLqde_jCw-C3y0fTvXMXX5W7QB0188bMvXVkRcedgTwY".to_string()]
+        );
+        assert_eq!(
+            changes[0].added,
+            vec!["D2M5V_e23Pa0IAqqhNDKzZX0kRIMkJyW8_M-gT_Kw9sc".to_string()]
+        );
+        assert!(resolver.apply_events(&events[4]).unwrap().is_empty());
+
+        let changes = resolver.apply_events(MULTISIG_KEL).unwrap();
+        assert_eq!(changes[0].previous_sn, None);
+        assert_eq!(changes[0].added.len(), 3);
+        assert!(changes[0].removed.is_empty());
+        assert_eq!(resolver.prefixes().unwrap().len(), 2);
+        // rejected event leaves resolver as it was
+        let tampered = ROTATED_KEL.replace("AAqHtncya5PNnwSbMRe", "AAqHtncya5PNnwSbMRf");
+        let mut fresh = DidKeriResolver::new(&events[0]).unwrap();
+        assert!(fresh.apply_events(&tampered).is_err());
+        assert_eq!(
+            fresh.key_state(&format!("did:keri:{}", prefix)).unwrap().sn,
+            0
+        );
+    }
+
+    #[test]
+    fn resolve_delegated_identifier_test() {
+        let did = "did:keri:E-9tsnVcfUyXVQyBPGfntoL-xexf4Cldt_EPzHis2W4U";
+        let delegator = "did:keri:Eta8KLf1zrE5n-HZpgRAnDmxLASZdXEiU9u6aahqR8TI";
+        let url = format!(
+            "{}?kerl={}",
+            did,
+            encode(&[DELEGATOR_KEL, DELEGATED_KEL].concat())
+        );
+        let doc = try_resolve_any(&url).unwrap();
+        assert_eq!(doc.id, did);
+        assert_eq!(
+            doc.find_public_key_controller_for_curve("Ed25519"),
+            Some(delegator.to_string())
+        );
+        let state = DidKeriResolver::new(&[DELEGATOR_KEL, DELEGATED_KEL].concat())
+            .unwrap()
+            .key_state(did)
+            .unwrap();
+        assert_eq!(state.sn, 1);
+        assert_eq!(
+            state.public_keys,
+            vec!["DTf6QZWoet154o9wvzeMuNhLQRr8JaAUeiC6wjB_4_08".to_string()]
+        );
+        assert_eq!(
+            state.delegator,
+            Some("Eta8KLf1zrE5n-HZpgRAnDmxLASZdXEiU9u6aahqR8TI".into())
+        );
+        // delegator itself is not delegated
+        let doc = try_resolve_any(&url.replace(did, delegator)).unwrap();
+        assert_eq!(doc.verification_method[0].controller, delegator);
+
+        // delegator KEL is looked up in the store
+        let resolver = DidKeriResolver::new(DELEGATOR_KEL).unwrap();
+        resolver.ingest(DELEGATED_KEL).unwrap();
+        assert_eq!(resolver.key_state(did).unwrap().sn, 1);
+    }
+
+    #[test]
+    fn reject_unanchored_delegated_events_test() {
+        let delegator = split_events(DELEGATOR_KEL);
+        let delegated = split_events(DELEGATED_KEL);
+        let drt_seal = "-GAB0AAAAAAAAAAAAAAAAAAAAAAgEbOI0OIIFv2VV5bmeSq1pwCn-6b2k6TdWcCbJHE6Ly7o";
+        assert!(delegated[1].ends_with(drt_seal));
+        assert!(DidKeriResolver::new(
+            &[delegator[0].as_str(), &delegator[1], &delegated[0]].concat()
+        )
+        .is_ok());
+        for kel in [
+            // no delegator KEL
+            delegated[0].clone(),
+            // delegator has not anchored inception yet
+            [delegator[0].as_str(), &delegated[0]].concat(),
+            // delegator has not anchored rotation yet
+            [
+                delegator[0].as_str(),
+                &delegator[1],
+                &delegated[0],
+                &delegated[1],
+            ]
+            .concat(),
+            // rotation points to event anchoring inception
+            [
+                DELEGATOR_KEL,
+                &delegated[0],
+                &delegated[1].replace(
+                    drt_seal,
+                    "-GAB0AAAAAAAAAAAAAAAAAAAAAAQE3fUycq1G-P1K1pL2OhvY6ZU-9otSa3hXiCcrxuhjyII",
+                ),
+            ]
+            .concat(),
+            // rotation without delegator seal
+            [
+                DELEGATOR_KEL,
+                &delegated[0],
+                &delegated[1].replace(drt_seal, ""),
+            ]
+            .concat(),
+        ] {
+            assert!(
+                matches!(DidKeriResolver::new(&kel), Err(Error::InvalidKel(_))),
+                "{} should be rejected",
+                kel
+            );
+        }
+    }
+
+    #[test]
+    fn reject_delegation_cycle_test() {
+        let (delegator, delegated) = (
+            "Eta8KLf1zrE5n-HZpgRAnDmxLASZdXEiU9u6aahqR8TI",
+            "E-9tsnVcfUyXVQyBPGfntoL-xexf4Cldt_EPzHis2W4U",
+        );
+        let dip = &split_events(DELEGATED_KEL)[0];
+        // tampered store, where delegator is delegated by its delegate
+        let store = MemoryKelStore::default();
+        store.append(delegated, dip.as_bytes()).unwrap();
+        let swapped = dip
+            .replace(delegator, "<delegator>")
+            .replace(delegated, delegator)
+            .replace("<delegator>", delegated);
+        store.append(delegator, swapped.as_bytes()).unwrap();
+        let resolver = DidKeriResolver::with_store(store);
+        match resolver.resolve(&format!("did:keri:{}", delegated)) {
+            Err(Error::InvalidKel(reason)) => assert!(reason.contains("delegation cycle")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn witness_receipts_threshold_test() {
+        let did = "did:keri:EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw";
+        let resolver = DidKeriResolver::new(WITNESSED_KEL).unwrap();
+        assert!(matches!(resolver.resolve(did), Err(Error::NotFound(_))));
+        resolver.ingest(RECEIPTS[0][0]).unwrap();
+        assert!(matches!(resolver.resolve(did), Err(Error::NotFound(_))));
+        // receipts are counted once per witness
+        assert!(resolver.ingest(RECEIPTS[0][0]).unwrap().is_empty());
+        resolver.ingest(RECEIPTS[0][1]).unwrap();
+        let state = resolver.key_state(did).unwrap();
+        assert_eq!(state.sn, 0);
+        assert_eq!(state.witnesses.len(), 3);
+        assert_eq!(state.witness_threshold, 2);
+        let res = resolver.resolve_with_metadata(did);
+        assert_eq!(res.did_document_metadata.version_id, Some("0".into()));
+        assert_eq!(res.did_document_metadata.next_version_id, None);
+
+        resolver
+            .ingest(&[RECEIPTS[1][0], RECEIPTS[1][2]].concat())
+            .unwrap();
+        assert_eq!(resolver.key_state(did).unwrap().sn, 1);
+        assert!(matches!(
+            resolver.resolve(&format!("{}?versionId=2", did)),
+            Err(Error::NotFound(_))
+        ));
+        // last witness is removed by rotation
+        assert!(matches!(
+            resolver.ingest(RECEIPTS[2][2]),
+            Err(Error::InvalidKel(_))
+        ));
+        resolver
+            .ingest(&[RECEIPTS[2][0], RECEIPTS[2][1]].concat())
+            .unwrap();
+        let state = resolver.key_state(did).unwrap();
+        assert_eq!(state.sn, 2);
+        assert_eq!(state.witnesses.len(), 2);
+    }
+
+    #[test]
+    fn validator_receipts_test() {
+        let did = "did:keri:E_GxP-4GzuC0iBbjqfPk4jv_YuFtfiAtGscfX0zxmt3k";
+        let resolver = DidKeriResolver::new(VALIDATED_KEL).unwrap();
+        assert!(matches!(resolver.resolve(did), Err(Error::NotFound(_))));
+        // KEL of witness is not known yet
+        assert!(matches!(
+            resolver.ingest(VALIDATOR_RECEIPT),
+            Err(Error::InvalidKel(_))
+        ));
+        resolver.ingest(VALIDATOR_KEL).unwrap();
+        assert_eq!(
+            resolver.ingest(VALIDATOR_RECEIPT).unwrap(),
+            vec!["E_GxP-4GzuC0iBbjqfPk4jv_YuFtfiAtGscfX0zxmt3k"]
+        );
+        assert_eq!(resolver.key_state(did).unwrap().witness_threshold, 1);
+        assert!(resolver.resolve(did).is_ok());
+        // same in single stream, receipt is verified again once stored
+        let resolver =
+            DidKeriResolver::new(&[VALIDATOR_KEL, VALIDATED_KEL, VALIDATOR_RECEIPT].concat())
+                .unwrap();
+        assert!(resolver.resolve(did).is_ok());
+    }
+
+    #[test]
+    fn reject_invalid_validator_receipts_test() {
+        // sealed by inception of witness, which has rotated keys since
+        let stale = r#"{"v":"KERI10JSON000091_","i":"E_GxP-4GzuC0iBbjqfPk4jv_YuFtfiAtGscfX0zxmt3k","s":"0","t":"rct","d":"E0JwZMM1p8_Kdl8H4sO_BQegpc8OZhxbG0srOnildYl0"}-FABD0l2zRzepgqu1JoUlaDo5U9pf5dtOnBDs5Xfyr3iF-Co0AAAAAAAAAAAAAAAAAAAAAAAElpTORII6TvzgzXhC_Ki-qzi-CV1ZJYTzu8Ui5Sv414E-AABAA4Wh5uo16AfhuMZyKMCykW4JiOapJ5LgB-zV0uKKXqF8A5_UxYY2cuftuiTP8U3E3AmPUm3Rd3lLB_k-FQJYwDQ"#;
+        // sealed by rotation, but signed with keys of inception
+        let wrong_key = r#"{"v":"KERI10JSON000091_","i":"E_GxP-4GzuC0iBbjqfPk4jv_YuFtfiAtGscfX0zxmt3k","s":"0","t":"rct","d":"E0JwZMM1p8_Kdl8H4sO_BQegpc8OZhxbG0srOnildYl0"}-FABD0l2zRzepgqu1JoUlaDo5U9pf5dtOnBDs5Xfyr3iF-Co0AAAAAAAAAAAAAAAAAAAAAAQEcpL6jI7KhZf9RiJknwmMwxnnfahI2zcjbwFO_nUHglo-AABAA4Wh5uo16AfhuMZyKMCykW4JiOapJ5LgB-zV0uKKXqF8A5_UxYY2cuftuiTP8U3E3AmPUm3Rd3lLB_k-FQJYwDQ"#;
+        let resolver = DidKeriResolver::new(&[VALIDATOR_KEL, VALIDATED_KEL].concat()).unwrap();
+        for vrc in [stale, wrong_key] {
+            match resolver.ingest(vrc) {
+                Err(Error::InvalidKel(reason)) => {
+                    assert!(reason.contains("D0l2zRzepgqu1JoUlaDo5U9pf5dtOnBDs5Xfyr3iF-Co"))
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+        assert!(matches!(
+            resolver.key_state("did:keri:E_GxP-4GzuC0iBbjqfPk4jv_YuFtfiAtGscfX0zxmt3k"),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn reject_invalid_receipts_test() {
+        let events = split_events(WITNESSED_KEL);
+        let resolver = DidKeriResolver::new(&events[0]).unwrap();
+        // receipt of event which is not ingested yet
+        assert!(matches!(
+            resolver.ingest(RECEIPTS[1][0]),
+            Err(Error::InvalidKel(_))
+        ));
+        let (body, couplet) = RECEIPTS[0][0].split_at(RECEIPTS[0][0].find("-CAB").unwrap());
+        let tampered = [body, &couplet[..couplet.len() - 4], "AAAA"].concat();
+        assert!(matches!(
+            resolver.ingest(&tampered),
+            Err(Error::InvalidKel(_))
+        ));
+        // receipt of other event with sequence number of inception
+        let other = RECEIPTS[1][0].replace(r#""s":"1""#, r#""s":"0""#);
+        assert!(matches!(resolver.ingest(&other), Err(Error::InvalidKel(_))));
+        // nothing is stored from stream with invalid receipt
+        assert!(resolver
+            .ingest(&[RECEIPTS[0][1], &tampered].concat())
+            .is_err());
+        resolver.ingest(RECEIPTS[0][1]).unwrap();
+        assert!(matches!(
+            resolver.key_state("did:keri:EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw"),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn apply_witnessed_events_test() {
+        let events = split_events(WITNESSED_KEL);
+        let mut resolver = DidKeriResolver::with_store(MemoryKelStore::default());
+        assert!(resolver
+            .apply_events(&events[..2].concat())
+            .unwrap()
+            .is_empty());
+        let changes = resolver
+            .apply_events(&[RECEIPTS[0][1], RECEIPTS[0][2], RECEIPTS[1][0]].concat())
+            .unwrap();
+        assert_eq!(changes[0].previous_sn, None);
+        assert_eq!(changes[0].state.sn, 0);
+        assert_eq!(changes[0].added.len(), 1);
+        let changes = resolver
+            .apply_events(&[RECEIPTS[1][1].to_string(), events[2].clone()].concat())
+            .unwrap();
+        assert_eq!(changes[0].previous_sn, Some(0));
+        assert_eq!(changes[0].state.sn, 1);
+        assert!(changes[0].added.is_empty());
+
+        let url = format!(
+            "did:keri:EBJ-kcTaw-pvMLGPkkg48WSNR_JQPzWvhRfZVgykc9mw?kerl={}",
+            encode(
+                &[WITNESSED_KEL, &RECEIPTS.concat().concat()]
+                    .concat()
+                    .replace(RECEIPTS[2][2], "")
+            )
+        );
+        let res = resolve_with_metadata(&url);
+        assert_eq!(res.did_document_metadata.version_id, Some("2".into()));
+    }
+
+    #[test]
+    fn x25519_key_agreement_test() {
+        let did = "did:keri:ENRHENIVTtS1VmS1_a04BDgdsmCf1aff1-tZvfT_f4sU";
+        let kel = r#"{"v":"KERI10JSON00011c_","i":"ENRHENIVTtS1VmS1_a04BDgdsmCf1aff1-tZvfT_f4sU","s":"0","t":"icp","kt":"1","k":["DMXkLnbZZ2g_oWGzaVz7LLmqtLpI72Y4GYsBsgJfBjF4","Cz-LsoY7B6foopEV_4Cpj0ubK3VIlJ_dELmjlwmirDuU"],"n":"EiZOdQzNE8-jGNfeAFAhb7T39eyxFy0lNXE-wYzAAVLA","bt":"0","b":[],"c":[],"a":[]}-AABAA9-soOfrjhPJE4bzlzhqSYKOIAAfTPzDM7ZNskZQ323IktarZYpc1NU178tAIYFErpDt6hoDbeE9dBsDXd3BJCw"#;
+        let doc = DidKeriResolver::new(kel).unwrap().resolve(did).unwrap();
+        assert!(doc.is_authorized_for("#0", Relationship::Authentication));
+        assert!(!doc.is_authorized_for("#1", Relationship::Authentication));
+        let agreement = doc.find_key_agreement("#1").unwrap();
+        assert_eq!(agreement.id, format!("{}#1", did));
+        assert_eq!(agreement.public_key().map(|k| k.len()), Some(32));
+    }
+}
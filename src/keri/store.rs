@@ -0,0 +1,141 @@
+use crate::Error;
+use std::{collections::BTreeMap, path::Path, sync::RwLock};
+
+/// Storage of key event logs, which `DidKeriResolver` resolves
+///     identifiers from by their prefix.
+/// Store keeps signed events (CESR stream of event with attached
+///     signatures) in order they were appended. It does not validate
+///     them - `DidKeriResolver::ingest()` does before appending, and
+///     resolution validates them again when KEL is read.
+/// Implementations must be safe to share between threads, as resolver
+///     may be registered in `ResolverRegistry` and still be ingested into.
+///
+pub trait KelStore: Send + Sync {
+    /// Prefixes of all identifiers which have events in the store.
+    ///
+    fn prefixes(&self) -> Result<Vec<String>, Error>;
+
+    /// KEL of identifier `prefix` as appended, empty if it has no events.
+    ///
+    fn kel(&self, prefix: &str) -> Result<Vec<u8>, Error>;
+
+    /// Appends signed `event` to KEL of identifier `prefix`.
+    ///
+    fn append(&self, prefix: &str, event: &[u8]) -> Result<(), Error>;
+}
+
+/// `KelStore` which keeps logs in memory only.
+///
+#[derive(Default)]
+pub struct MemoryKelStore {
+    kels: RwLock<BTreeMap<String, Vec<u8>>>,
+}
+
+impl KelStore for MemoryKelStore {
+    fn prefixes(&self) -> Result<Vec<String>, Error> {
+        Ok(self
+            .kels
+            .read()
+            .map_err(poisoned)?
+            .keys()
+            .cloned()
+            .collect())
+    }
+
+    fn kel(&self, prefix: &str) -> Result<Vec<u8>, Error> {
+        Ok(self
+            .kels
+            .read()
+            .map_err(poisoned)?
+            .get(prefix)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn append(&self, prefix: &str, event: &[u8]) -> Result<(), Error> {
+        self.kels
+            .write()
+            .map_err(poisoned)?
+            .entry(prefix.into())
+            .or_default()
+            .extend_from_slice(event);
+        Ok(())
+    }
+}
+
+// Lock is poisoned only if other thread panicked while appending
+fn poisoned<T>(_: std::sync::PoisonError<T>) -> Error {
+    Error::InternalError("KEL store lock is poisoned".into())
+}
+
+/// `KelStore` persisted on disk in `sled` database, one key per
+///     identifier prefix. Every append is flushed before it returns.
+///
+pub struct SledKelStore {
+    db: sled::Db,
+}
+
+impl SledKelStore {
+    /// Opens (creating if missing) database at `path`.
+    ///
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(SledKelStore {
+            db: sled::open(path)?,
+        })
+    }
+}
+
+impl KelStore for SledKelStore {
+    fn prefixes(&self) -> Result<Vec<String>, Error> {
+        self.db
+            .iter()
+            .keys()
+            .map(|key| Ok(String::from_utf8_lossy(&key?).into_owned()))
+            .collect()
+    }
+
+    fn kel(&self, prefix: &str) -> Result<Vec<u8>, Error> {
+        Ok(self
+            .db
+            .get(prefix)?
+            .map(|kel| kel.to_vec())
+            .unwrap_or_default())
+    }
+
+    fn append(&self, prefix: &str, event: &[u8]) -> Result<(), Error> {
+        self.db.update_and_fetch(prefix, |kel| {
+            Some([kel.unwrap_or_default(), event].concat())
+        })?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod kel_store_tests {
+    use super::*;
+
+    fn append_and_read(store: &dyn KelStore) {
+        assert!(store.prefixes().unwrap().is_empty());
+        assert!(store.kel("Eabc").unwrap().is_empty());
+        store.append("Eabc", b"first").unwrap();
+        store.append("Dxyz", b"other").unwrap();
+        store.append("Eabc", b"second").unwrap();
+        assert_eq!(store.kel("Eabc").unwrap(), b"firstsecond".to_vec());
+        assert_eq!(store.prefixes().unwrap(), vec!["Dxyz", "Eabc"]);
+    }
+
+    #[test]
+    fn memory_store_test() {
+        append_and_read(&MemoryKelStore::default());
+    }
+
+    #[test]
+    fn sled_store_test() {
+        let dir = tempfile::tempdir().unwrap();
+        append_and_read(&SledKelStore::open(dir.path()).unwrap());
+        // reopened store has the events
+        let store = SledKelStore::open(dir.path()).unwrap();
+        assert_eq!(store.kel("Dxyz").unwrap(), b"other".to_vec());
+    }
+}
@@ -1,5 +1,8 @@
-use crate::{DdoResolver, Error};
+use crate::{error, AsyncDdoResolver, DdoResolver, DidUrl};
+use async_trait::async_trait;
+use base58::FromBase58;
 pub use did_key::*;
+use std::convert::TryFrom;
 
 /// Unit struct which have implementations of `DdoParser` and `DdoResolver`
 ///     traits for `did:key` document resolver.
@@ -7,16 +10,58 @@ pub use did_key::*;
 pub struct DidKeyResolver {}
 
 impl DdoResolver for DidKeyResolver {
-    fn resolve(&self, did_url: &str) -> Result<Document, Error> {
-        let key = did_key::resolve(did_url).map_err(|e| Error::DidKeyError(format!("{:?}", e)))?;
+    fn resolve(&self, did_url: &str) -> Result<Document, error::Error> {
+        let url: DidUrl = did_url.parse()?;
+        check_method_specific_id(&url.method_specific_id)?;
+        let key =
+            did_key::resolve(did_url).map_err(|e| error::Error::InvalidDid(format!("{:?}", e)))?;
         Ok(key.get_did_document(did_key::CONFIG_LD_PUBLIC))
     }
 }
 
+// Checks multibase encoding, multicodec prefix and public key of
+//  `did:key` method specific id, as `did_key` crate panics on those.
+// Secp256k1 keys are rejected, as `did_key` only knows them with
+//  invalid `e7 00` multicodec prefix and would derive wrong ids.
+fn check_method_specific_id(id: &str) -> Result<(), error::Error> {
+    let invalid = |reason: &str| error::Error::InvalidDid(format!("{}: did:key:{}", reason, id));
+    let bytes = id
+        .strip_prefix('z')
+        .and_then(|key| key.from_base58().ok())
+        .ok_or_else(|| invalid("not a base58btc multibase key"))?;
+    let key = bytes.get(2..).unwrap_or_default();
+    let valid = match bytes.get(..2) {
+        Some([0xed, 0x01]) => ed25519_dalek::PublicKey::from_bytes(key).is_ok(),
+        Some([0xec, 0x01]) => key.len() == 32,
+        Some([0x80, 0x24]) => p256::EncodedPoint::from_bytes(key)
+            .ok()
+            .and_then(|point| p256::ecdsa::VerifyingKey::from_encoded_point(&point).ok())
+            .is_some(),
+        Some([0xee, 0x01]) => {
+            key.len() == 144
+                && bbs::prelude::DeterministicPublicKey::try_from(key[48..].to_vec()).is_ok()
+        }
+        Some([0xe7, 0x01]) => return Err(invalid("secp256k1 keys are not supported")),
+        _ => return Err(invalid("unsupported key type")),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid("invalid public key"))
+    }
+}
+
+#[async_trait]
+impl AsyncDdoResolver for DidKeyResolver {
+    async fn resolve_async(&self, did_url: &str) -> Result<Document, error::Error> {
+        self.resolve(did_url)
+    }
+}
+
 #[cfg(test)]
 mod did_key_tests {
     use super::*;
-    use crate::{resolve_any, DdoParser};
+    use crate::{resolve_any, resolve_any_async, resolve_with_metadata, DdoParser};
 
     #[test]
     fn did_key_resolve_raw_test() {
@@ -38,6 +83,55 @@ mod did_key_tests {
         assert!(d.is_some());
     }
 
+    #[tokio::test]
+    async fn did_key_resolve_async_trait_test() {
+        let r = DidKeyResolver {}
+            .resolve_async("did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp")
+            .await;
+        assert!(r.is_ok());
+    }
+
+    #[tokio::test]
+    async fn resolve_any_async_test() {
+        let d = resolve_any_async("did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp").await;
+        assert!(d.is_some());
+    }
+
+    #[test]
+    fn resolve_with_metadata_test() {
+        let r = resolve_with_metadata("did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp");
+        assert!(r.is_ok());
+        assert!(r.did_resolution_metadata.error.is_none());
+        let r = resolve_with_metadata("not:a:did");
+        assert!(r.did_document.is_none());
+        assert!(r.did_resolution_metadata.error.is_some());
+    }
+
+    #[test]
+    fn invalid_did_key_test() {
+        for did in [
+            "did:key:6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp",
+            "did:key:z0OIl",
+            "did:key:z6Mk",
+            "did:key:z6Mkeb4rtEhc8DUtvt5ehaVjdx3TLbQPpnTArkXhqfb1Mq75",
+            "did:key:z4oJ8ZnAi4ZWyiXBMCb2X58sTBdBPSsnwcfJcvAE6L57wW1WsvWZCbUwLCFDGai7YmR3t151yVUGCVLDDNxE7h9UWNJWK",
+        ] {
+            assert!(
+                matches!(
+                    DidKeyResolver {}.resolve(did),
+                    Err(error::Error::InvalidDid(_))
+                ),
+                "{} should be rejected",
+                did
+            );
+        }
+        let r = resolve_with_metadata("did:key:z6Mk");
+        assert_eq!(r.did_resolution_metadata.error, Some("invalidDid".into()));
+        // secp256k1 key type is not supported, did:key itself is
+        let r = resolve_with_metadata("did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme");
+        assert_eq!(r.did_resolution_metadata.error, Some("invalidDid".into()));
+    }
+
     #[test]
     fn public_key_by_type_search_test() {
         let d = resolve_any("did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp");
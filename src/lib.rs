@@ -1,29 +1,45 @@
+pub mod dereference;
+pub mod didurl;
+pub mod document;
 pub mod error;
+#[cfg(any(feature = "jolo", feature = "ethr"))]
+mod ethereum;
+#[cfg(feature = "ethr")]
+pub mod ethr;
 
 #[cfg(feature = "jolo")]
 pub mod jolo;
+#[cfg(feature = "didjwk")]
+pub mod jwk;
 #[cfg(feature = "keriox")]
 pub mod keri;
 #[cfg(feature = "didkey")]
 pub mod key;
+#[cfg(feature = "peer")]
+pub mod peer;
+#[cfg(feature = "didpkh")]
+pub mod pkh;
+pub mod registry;
+pub mod resolution;
+#[cfg(feature = "didweb")]
+pub mod web;
 
-#[cfg(feature = "keriox")]
-use crate::keri::DidKeriResolver;
-#[cfg(feature = "didkey")]
-use key::DidKeyResolver;
-
-use base58::FromBase58;
-pub use did_key::{Document, KeyFormat, VerificationMethod};
+use async_trait::async_trait;
+use base58::{FromBase58, ToBase58};
+pub use dereference::{dereference, DereferencedResource, DereferencingResult};
+pub use did_key::{Document, KeyFormat, VerificationMethod, JWK};
+pub use didurl::DidUrl;
+use document::{absolute_id, resolve_relationship_entry};
+pub use document::{document_from_json, Relationship, Service};
 use error::Error;
 use lazy_static::lazy_static;
-use regex::Regex;
+use registry::ResolverRegistry;
+pub use resolution::{DocumentMetadata, ResolutionMetadata, ResolutionResult};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 lazy_static! {
-    static ref DID_REGEX: Regex = Regex::new(
-        r"(?x)(?P<prefix>[did]{3}):(?P<method>[a-z]*):(?P<key_id>[-_a-zA-Z0-9]*)([:?/]?)(S)*??",
-    )
-    .unwrap();
+    static ref DEFAULT_REGISTRY: ResolverRegistry = ResolverRegistry::default();
 }
 
 /// # Universal trait for DID document resolver.
@@ -38,6 +54,70 @@ pub trait DdoResolver {
     ///     https://www.w3.org/TR/did-core/#did-url-syntax
     ///
     fn resolve(&self, did_url: &str) -> Result<Document, Error>;
+    /// Resolves `did_url` into full DID Resolution result, which in addition
+    ///     to the document carries resolution and document metadata.
+    /// Default implementation wraps output of `resolve()` without any
+    ///     document metadata, resolvers which know more should override it.
+    ///
+    fn resolve_with_metadata(&self, did_url: &str) -> ResolutionResult {
+        self.resolve(did_url).into()
+    }
+    /// Services of `did_url`. `Document` has no services section, so
+    ///     resolvers of methods which define services override this
+    ///     to expose them. Default implementation has none.
+    ///
+    fn services(&self, _did_url: &str) -> Result<Vec<Service>, Error> {
+        Ok(vec![])
+    }
+}
+
+/// # Universal asynchronous trait for DID document resolver.
+/// Same as `DdoResolver`, but does not block the caller, so resolution
+///     can be composed with other futures inside of an existing runtime.
+///
+#[async_trait]
+pub trait AsyncDdoResolver {
+    /// Method signature for asynchronous DID Document resolver.
+    ///
+    /// # Parameters
+    /// `did_url` - proper DID url starts with "did:" followed up by
+    ///     method name, path, etc. Details in spec:
+    ///     https://www.w3.org/TR/did-core/#did-url-syntax
+    ///
+    async fn resolve_async(&self, did_url: &str) -> Result<Document, Error>;
+    /// Asynchronous version of `DdoResolver::resolve_with_metadata()`.
+    ///
+    async fn resolve_with_metadata_async(&self, did_url: &str) -> ResolutionResult {
+        self.resolve_async(did_url).await.into()
+    }
+}
+
+/// Shared resolver, so single instance can be registered for both
+///     synchronous and asynchronous resolution in `ResolverRegistry`.
+///
+impl<T: DdoResolver + ?Sized> DdoResolver for Arc<T> {
+    fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+        (**self).resolve(did_url)
+    }
+
+    fn resolve_with_metadata(&self, did_url: &str) -> ResolutionResult {
+        (**self).resolve_with_metadata(did_url)
+    }
+
+    fn services(&self, did_url: &str) -> Result<Vec<Service>, Error> {
+        (**self).services(did_url)
+    }
+}
+
+#[async_trait]
+impl<T: AsyncDdoResolver + Send + Sync + ?Sized> AsyncDdoResolver for Arc<T> {
+    async fn resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+        (**self).resolve_async(did_url).await
+    }
+
+    async fn resolve_with_metadata_async(&self, did_url: &str) -> ResolutionResult {
+        (**self).resolve_with_metadata_async(did_url).await
+    }
 }
 
 /// # Universal trait for DID document parser methods.
@@ -45,8 +125,11 @@ pub trait DdoResolver {
 ///     for particular elements or public crypto material.
 ///
 pub trait DdoParser {
-    /// Pattern finding method to resolve `KeyAgreement` based on provided
-    /// `pattern`. Returns `None` if no matching result found instead of error.
+    /// Finds `KeyAgreement` with id or type exactly matching `pattern`.
+    ///     Id can be either absolute or relative (`#id`) to the document id.
+    /// Both embedded entries and references to `verification_method`s
+    ///     of `key_agreement` are searched.
+    /// Returns `None` if no matching result found instead of error.
     ///
     fn find_key_agreement(&self, pattern: &str) -> Option<KeyAgreement>;
     /// Searches all crypto matherial in the document for particular curve and
@@ -63,56 +146,67 @@ pub trait DdoParser {
     /// Returns `None` if no matching curve found.
     ///
     fn find_public_key_controller_for_curve(&self, curve: &str) -> Option<String>;
+    /// Verification methods authorised for `relationship`, both embedded
+    ///     and referenced by id from `verification_method`s.
+    /// References which do not match any method are skipped.
+    ///
+    fn keys_for_relationship(&self, relationship: Relationship) -> Vec<VerificationMethod>;
+    /// Checks if verification method with `key_id` (absolute or relative
+    ///     to the document id) is authorised for `relationship`.
+    ///
+    fn is_authorized_for(&self, key_id: &str, relationship: Relationship) -> bool;
 }
 
 impl DdoParser for Document {
     fn find_key_agreement(&self, pattern: &str) -> Option<KeyAgreement> {
-        let agreements = self.key_agreement.clone();
-        if agreements.is_none() {
-            return None;
-        }
-        match agreements.unwrap().iter().find(|a| a.contains(pattern)) {
-            Some(a) => serde_json::from_str(a).unwrap_or(None),
-            None => None,
-        }
+        let id = absolute_id(&self.id, pattern);
+        self.key_agreement
+            .iter()
+            .flatten()
+            .filter_map(|entry| {
+                if entry.trim_start().starts_with('{') {
+                    serde_json::from_str(entry).ok()
+                } else {
+                    resolve_relationship_entry(self, entry).map(|vm| KeyAgreement::from(&vm))
+                }
+            })
+            .find(|ka| absolute_id(&self.id, &ka.id) == id || ka.r#type == pattern)
     }
     fn find_public_key_for_curve(&self, curve: &str) -> Option<Vec<u8>> {
-        if let Some(k) = self
-            .verification_method
+        self.verification_method
             .iter()
-            .find(|m| m.key_type.contains(curve))
-        {
-            if let Some(key) = k.public_key.clone() {
-                match key {
-                    KeyFormat::Base58(value) => Some(value.from_base58().unwrap()),
-                    KeyFormat::Multibase(value) => Some(value),
-                    KeyFormat::JWK(_value) => todo!(), // FIXME: proper return should be implemented
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+            .find(|vm| {
+                vm.key_type.contains(curve)
+                    || matches!(&vm.public_key, Some(KeyFormat::JWK(jwk)) if jwk.curve.contains(curve))
+            })
+            .and_then(|vm| vm.public_key.as_ref())
+            .and_then(public_key_bytes)
     }
     fn find_public_key_id_for_curve(&self, curve: &str) -> Option<String> {
         match get_public_key(self, curve) {
-            Some(kf) => match kf {
-                KeyFormat::JWK(key) => key.key_id,
-                _ => None,
-            },
-            None => None,
+            Some(KeyFormat::JWK(key)) => key.key_id,
+            _ => None,
         }
     }
     fn find_public_key_controller_for_curve(&self, curve: &str) -> Option<String> {
-        match self
-            .verification_method
+        self.verification_method
             .iter()
             .find(|vm| vm.key_type.contains(curve))
-        {
-            Some(vm) => Some(vm.controller.to_owned()),
-            None => None,
-        }
+            .map(|vm| vm.controller.to_owned())
+    }
+    fn keys_for_relationship(&self, relationship: Relationship) -> Vec<VerificationMethod> {
+        relationship
+            .entries(self)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| resolve_relationship_entry(self, entry))
+            .collect()
+    }
+    fn is_authorized_for(&self, key_id: &str, relationship: Relationship) -> bool {
+        let key_id = absolute_id(&self.id, key_id);
+        self.keys_for_relationship(relationship)
+            .iter()
+            .any(|vm| absolute_id(&self.id, &vm.id) == key_id)
     }
 }
 
@@ -121,33 +215,12 @@ impl DdoParser for Document {
 /// This function provides convenience but is dependant on resolver features enabled
 ///     and will have overhead comparing to direct trait call of specific resolver,
 ///     therefore should be used with consideration.
+/// Dispatches to `ResolverRegistry::default()`, build own `ResolverRegistry`
+///     to resolve methods which are not built in.
 /// Output is `Document` or `Error`.
 ///
 pub fn try_resolve_any(did_url: &str) -> Result<Document, Error> {
-    let re = regex::Regex::new(r"^((?P<prefix>did){1}:(?P<method>[-_A-Za-z0-9]*){1}:(?P<id>.+?))((?P<kerlid>\?kerl=)(?P<kerl>[a-zA-Z0-9]+?))?$").unwrap();
-    match re.captures(did_url) {
-        Some(caps) => {
-            match &caps["method"] {
-                #[cfg(feature = "didkey")]
-                "key" => DidKeyResolver {}
-                    .resolve(did_url)
-                    .map_err(|e| error::Error::DidKeyError(e.to_string())),
-                #[cfg(feature = "keriox")]
-                "keri" => match &caps["kerlid"] {
-                    "" => Err(error::Error::DidKeriError("kerl id not found".into())),
-                    _ => match &caps["kerl"] {
-                        "" => Err(error::Error::DidKeriError("kerl not found".into())),
-                        _ => DidKeriResolver::new(&String::from_utf8_lossy(&base64_url::decode(
-                            &caps["kerl"],
-                        )?))
-                        .resolve(&format!("did:keri:{}", &caps["id"])),
-                    },
-                },
-                _ => Err(error::Error::DidKeyError("not supported key url".into())), // TODO: separate descriptive error
-            }
-        }
-        None => Err(error::Error::DidKeyError("not a did url".into())), // TODO: separate descriptive error
-    }
+    DEFAULT_REGISTRY.try_resolve(did_url)
 }
 
 /// Helper function to try resolve any document based on provided `did_url` instead
@@ -158,47 +231,132 @@ pub fn try_resolve_any(did_url: &str) -> Result<Document, Error> {
 /// Output is Option: `Some(Document)` or `None`. Will never fail with error.
 ///
 pub fn resolve_any(did_url: &str) -> Option<Document> {
-    let re = regex::Regex::new(r"^((?P<prefix>did){1}:(?P<method>[-_a-zA-Z0-9]*){1}:(?P<id>.+?))((?P<kerlid>\?kerl=)(?P<kerl>[a-zA-Z0-9]+?))?$").unwrap();
-    match re.captures(did_url) {
-        Some(caps) => {
-            let resolver: Box<dyn DdoResolver> = match &caps["method"] {
-                #[cfg(feature = "didkey")]
-                "key" => Box::new(DidKeyResolver {}),
-                #[cfg(feature = "keriox")]
-                "keri" => Box::new(DidKeriResolver::new(&String::from_utf8_lossy(
-                    &base64_url::decode(&caps["kerl"]).unwrap_or(vec![]),
-                ))),
-                #[cfg(feature = "didjolo")]
-                "jolo" => {}
-                #[cfg(feature = "didweb")]
-                "web" => {}
+    DEFAULT_REGISTRY.resolve(did_url)
+}
+
+/// Same as `try_resolve_any`, but outputs full `ResolutionResult` with
+///     resolution and document metadata populated by resolver of the method.
+/// Will never fail, errors are reported in `did_resolution_metadata.error`.
+///
+pub fn resolve_with_metadata(did_url: &str) -> ResolutionResult {
+    DEFAULT_REGISTRY.resolve_with_metadata(did_url)
+}
+
+/// Asynchronous version of `try_resolve_any`.
+/// Dispatches `did_url` to `AsyncDdoResolver` of the method, so it can be
+///     awaited from within of an existing runtime.
+/// Output is `Document` or `Error`.
+///
+pub async fn try_resolve_any_async(did_url: &str) -> Result<Document, Error> {
+    DEFAULT_REGISTRY.try_resolve_async(did_url).await
+}
+
+/// Asynchronous version of `resolve_any`.
+/// Output is Option: `Some(Document)` or `None`. Will never fail with error.
+///
+pub async fn resolve_any_async(did_url: &str) -> Option<Document> {
+    try_resolve_any_async(did_url).await.ok()
+}
+
+/// Selects keys of the document by their purpose:
+///     signing keys are referenced from `authentication` and
+///     `assertionMethod`, encryption keys - from `keyAgreement`.
+/// Output is `(signing_keys, encryption_keys)` in order of references
+///     without duplicates. References which do not match any verification
+///     method or keys which can not be decoded are skipped.
+///
+pub fn get_sign_and_crypto_keys(ddo: &Document) -> (Vec<ResolvedKey>, Vec<ResolvedKey>) {
+    let sign_methods = [Relationship::Authentication, Relationship::AssertionMethod]
+        .iter()
+        .flat_map(|r| ddo.keys_for_relationship(*r));
+    (
+        decode_keys(sign_methods),
+        decode_keys(ddo.keys_for_relationship(Relationship::KeyAgreement)),
+    )
+}
+
+/// Public key of the document with raw key bytes decoded from
+///     any of `KeyFormat`s.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedKey {
+    pub id: String,
+    pub key_type: String,
+    pub controller: String,
+    pub public_key: Vec<u8>,
+}
+
+impl ResolvedKey {
+    /// Decodes public key of `vm`, `None` if it has no public key
+    ///     or it's malformed.
+    ///
+    pub fn from_verification_method(vm: &VerificationMethod) -> Option<Self> {
+        Some(Self {
+            id: vm.id.clone(),
+            key_type: vm.key_type.clone(),
+            controller: vm.controller.clone(),
+            public_key: public_key_bytes(vm.public_key.as_ref()?)?,
+        })
+    }
+}
+
+// Decodes keys of verification methods, skipping duplicates
+fn decode_keys(methods: impl IntoIterator<Item = VerificationMethod>) -> Vec<ResolvedKey> {
+    let mut keys: Vec<ResolvedKey> = vec![];
+    for key in methods
+        .into_iter()
+        .filter_map(|vm| ResolvedKey::from_verification_method(&vm))
+    {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+/// Decodes raw public key bytes of any `KeyFormat`.
+/// JWK keys are supported for "OKP" (Ed25519, X25519) and "EC" (secp256k1,
+///     P-256, P-384) key types, latter are returned as uncompressed
+///     SEC1 point (0x04 || x || y).
+/// Returns `None` if key is malformed or of unsupported type.
+///
+pub fn public_key_bytes(key: &KeyFormat) -> Option<Vec<u8>> {
+    match key {
+        KeyFormat::Base58(value) => value.from_base58().ok(),
+        KeyFormat::Multibase(value) => Some(value.clone()),
+        KeyFormat::JWK(jwk) => jwk_public_key_bytes(jwk),
+    }
+}
+
+// Decodes raw public key bytes of JWK with length checks for the curve
+fn jwk_public_key_bytes(jwk: &JWK) -> Option<Vec<u8>> {
+    let decode = |c: &Option<String>| c.as_ref().and_then(|c| base64_url::decode(c).ok());
+    let x = decode(&jwk.x)?;
+    match (jwk.key_type.as_str(), jwk.curve.to_lowercase().as_str()) {
+        ("OKP", "ed25519") | ("OKP", "x25519") if x.len() == 32 => Some(x),
+        ("EC", curve) => {
+            let len = match curve {
+                "secp256k1" | "p-256" => 32,
+                "p-384" => 48,
                 _ => return None,
             };
-            let parsed_url = format!("{}:{}:{}", &caps["prefix"], &caps["method"], &caps["id"]);
-            match resolver.resolve(&parsed_url) {
-                Ok(doc) => Some(doc),
-                Err(_) => None,
+            match decode(&jwk.y) {
+                Some(y) if x.len() == len && y.len() == len => {
+                    Some([&[0x04], x.as_slice(), y.as_slice()].concat())
+                }
+                // `did_key` puts whole SEC1 encoded point into `x` of P-256 keys
+                None if (x.len() == len + 1 && matches!(x[0], 0x02 | 0x03))
+                    || (x.len() == 2 * len + 1 && x[0] == 0x04) =>
+                {
+                    Some(x)
+                }
+                _ => None,
             }
         }
-        None => None,
+        _ => None,
     }
 }
 
-// FIXME: complete this implementation
-pub fn get_sign_and_crypto_keys<'a>(ddo: &'a Document) -> (Option<&'a [u8]>, Option<&'a [u8]>) {
-    let _sign_key = ddo.verification_method.iter().fold(None, |_, vm| {
-        vm.public_key.iter().find(|k| match k {
-            KeyFormat::JWK(key) => key.curve == "Ed25519",
-            _ => false,
-        })
-    });
-    let _crypto_key = ddo
-        .verification_method
-        .iter()
-        .find(|vm| vm.key_type == "X25519");
-    (None, None)
-}
-
 // Helper function to get full `KeyFormat` from the document by it's curve type
 pub(crate) fn get_public_key(doc: &Document, curve: &str) -> Option<KeyFormat> {
     match doc
@@ -213,40 +371,73 @@ pub(crate) fn get_public_key(doc: &Document, curve: &str) -> Option<KeyFormat> {
     }
 }
 
-// Helper function to get key id from did url
-// # + id
-pub(crate) fn key_id_from_didurl(url: &str) -> String {
-    match DID_REGEX.captures(url) {
-        Some(s) => match s.name("key_id") {
-            Some(name) => format!("#{}", name.as_str()),
-            None => String::default(),
-        },
-        None => String::default(),
-    }
-}
-
-// Parses and String formats prefix:method:key_id from given &str
+// Parses and String formats did:method:method_specific_id from given &str
 //
 pub fn did_id_from_url(url: &str) -> Option<String> {
-    let captures = DID_REGEX.captures(url)?;
-    Some(format!(
-        "{}:{}:{}",
-        captures.name("prefix")?.as_str(),
-        captures.name("method")?.as_str(),
-        captures.name("key_id")?.as_str()
-    ))
+    url.parse::<DidUrl>().ok().map(|url| url.did())
 }
 
 /// "Temporary" struct to extend did_key crate's `Document` with `KeyAgreement` instead of string.
 ///
 #[cfg(feature = "didkey")]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct KeyAgreement {
     pub id: String,
     pub r#type: String,
+    #[serde(default)]
     pub controller: String,
-    #[serde(rename = "publicKeyBase58")]
-    pub public_key_base58: String,
+    #[serde(rename = "publicKeyBase58", skip_serializing_if = "Option::is_none")]
+    pub public_key_base58: Option<String>,
+    #[serde(rename = "publicKeyMultibase", skip_serializing_if = "Option::is_none")]
+    pub public_key_multibase: Option<String>,
+    #[serde(rename = "publicKeyJwk", skip_serializing_if = "Option::is_none")]
+    pub public_key_jwk: Option<JWK>,
+}
+
+#[cfg(feature = "didkey")]
+impl KeyAgreement {
+    /// Raw public key bytes of whichever key format is present.
+    ///
+    pub fn public_key(&self) -> Option<Vec<u8>> {
+        if let Some(key) = &self.public_key_base58 {
+            key.from_base58().ok()
+        } else if let Some(key) = &self.public_key_multibase {
+            document::decode_multibase_key(key)
+        } else {
+            self.public_key_jwk.as_ref().and_then(jwk_public_key_bytes)
+        }
+    }
+}
+
+#[cfg(feature = "didkey")]
+impl From<&VerificationMethod> for KeyAgreement {
+    /// Multibase keys are (re)encoded as base58btc with multicodec
+    ///     prefix of the key type, or as `publicKeyBase58` if the type
+    ///     does not determine the prefix.
+    ///
+    fn from(vm: &VerificationMethod) -> Self {
+        let mut ka = KeyAgreement {
+            id: vm.id.clone(),
+            r#type: vm.key_type.clone(),
+            controller: vm.controller.clone(),
+            public_key_base58: None,
+            public_key_multibase: None,
+            public_key_jwk: None,
+        };
+        match &vm.public_key {
+            Some(KeyFormat::Base58(key)) => ka.public_key_base58 = Some(key.clone()),
+            Some(KeyFormat::Multibase(key)) => match document::multicodec_prefix(&vm.key_type) {
+                Some(prefix) => {
+                    ka.public_key_multibase =
+                        Some(format!("z{}", [&prefix, &key[..]].concat().to_base58()))
+                }
+                None => ka.public_key_base58 = Some(key.to_base58()),
+            },
+            Some(KeyFormat::JWK(key)) => ka.public_key_jwk = Some(key.clone()),
+            None => {}
+        }
+        ka
+    }
 }
 
 #[test]
@@ -274,3 +465,242 @@ fn did_id_from_url_test() {
     );
     assert!(did_id_from_url(not_a_did).is_none());
 }
+
+#[cfg(all(test, feature = "didkey"))]
+mod ddo_parser_tests {
+    use super::*;
+    use did_key::DIDCore;
+
+    const DID: &str = "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+
+    fn document_with(public_key: KeyFormat) -> Document {
+        Document {
+            context: "https://www.w3.org/ns/did/v1".into(),
+            id: "did:example:123".into(),
+            assertion_method: None,
+            authentication: None,
+            capability_delegation: None,
+            capability_invocation: None,
+            key_agreement: None,
+            verification_method: vec![VerificationMethod {
+                id: "did:example:123#key-1".into(),
+                key_type: "JsonWebKey2020".into(),
+                controller: "did:example:123".into(),
+                public_key: Some(public_key),
+                private_key: None,
+            }],
+        }
+    }
+
+    fn ec_jwk(curve: &str, x: &[u8], y: &[u8]) -> KeyFormat {
+        KeyFormat::JWK(JWK {
+            key_type: "EC".into(),
+            curve: curve.into(),
+            x: Some(base64_url::encode(x)),
+            y: Some(base64_url::encode(y)),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn base58_public_key_test() {
+        let ld = did_key::resolve(DID)
+            .unwrap()
+            .get_did_document(did_key::CONFIG_LD_PUBLIC);
+        let key = ld.find_public_key_for_curve("Ed25519").unwrap();
+        assert_eq!(key.len(), 32);
+        let broken = document_with(KeyFormat::Base58("0OIl".into()));
+        assert!(broken.find_public_key_for_curve("JsonWebKey").is_none());
+    }
+
+    #[test]
+    fn multibase_public_key_test() {
+        let doc = document_with(KeyFormat::Multibase(vec![1, 2, 3]));
+        assert_eq!(
+            doc.find_public_key_for_curve("JsonWebKey"),
+            Some(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn okp_jwk_public_key_test() {
+        let ld = did_key::resolve(DID)
+            .unwrap()
+            .get_did_document(did_key::CONFIG_LD_PUBLIC);
+        let jose = did_key::resolve(DID)
+            .unwrap()
+            .get_did_document(did_key::CONFIG_JOSE_PUBLIC);
+        for curve in ["Ed25519", "X25519"] {
+            let key = jose.find_public_key_for_curve(curve);
+            assert!(key.is_some());
+            assert_eq!(key, ld.find_public_key_for_curve(curve));
+        }
+        let short = document_with(KeyFormat::JWK(JWK {
+            key_type: "OKP".into(),
+            curve: "Ed25519".into(),
+            x: Some(base64_url::encode(&[1u8; 31])),
+            ..Default::default()
+        }));
+        assert!(short.find_public_key_for_curve("Ed25519").is_none());
+    }
+
+    #[test]
+    fn sign_and_crypto_keys_test() {
+        for config in [did_key::CONFIG_LD_PUBLIC, did_key::CONFIG_JOSE_PUBLIC] {
+            let doc = did_key::resolve(DID).unwrap().get_did_document(config);
+            let (sign, crypto) = get_sign_and_crypto_keys(&doc);
+            assert_eq!(sign.len(), 1);
+            assert_eq!(sign[0].id, format!("{}#{}", DID, &DID[8..]));
+            assert_eq!(sign[0].controller, DID);
+            assert_eq!(
+                Some(&sign[0].public_key),
+                doc.find_public_key_for_curve("Ed25519").as_ref()
+            );
+            assert_eq!(crypto.len(), 1);
+            assert!(crypto[0].id.starts_with(&format!("{}#z6LS", DID)));
+            assert_eq!(crypto[0].public_key.len(), 32);
+        }
+    }
+
+    #[test]
+    fn relative_key_references_test() {
+        let mut doc = document_with(ec_jwk("P-256", &[1; 32], &[2; 32]));
+        doc.verification_method[0].id = "#key-1".into();
+        doc.assertion_method = Some(vec!["did:example:123#key-1".into()]);
+        doc.authentication = Some(vec!["#key-1".into(), "#missing".into()]);
+        let (sign, crypto) = get_sign_and_crypto_keys(&doc);
+        assert_eq!(sign.len(), 1);
+        assert_eq!(sign[0].public_key.len(), 65);
+        assert!(crypto.is_empty());
+    }
+
+    #[test]
+    fn keys_for_relationship_test() {
+        let doc = did_key::resolve(DID)
+            .unwrap()
+            .get_did_document(did_key::CONFIG_LD_PUBLIC);
+        let auth = doc.keys_for_relationship(Relationship::Authentication);
+        assert_eq!(auth.len(), 1);
+        assert_eq!(auth[0].key_type, "Ed25519VerificationKey2018");
+        let agreement = doc.keys_for_relationship(Relationship::KeyAgreement);
+        assert_eq!(agreement.len(), 1);
+        assert_eq!(agreement[0].key_type, "X25519KeyAgreementKey2019");
+        assert!(doc.is_authorized_for(&auth[0].id, Relationship::CapabilityInvocation));
+        assert!(!doc.is_authorized_for(&auth[0].id, Relationship::KeyAgreement));
+        assert!(!doc.is_authorized_for(&agreement[0].id, Relationship::Authentication));
+    }
+
+    #[test]
+    fn embedded_relationship_entry_test() {
+        let mut doc = document_with(KeyFormat::Base58("11".into()));
+        doc.authentication = Some(vec![
+            "#key-1".into(),
+            r##"{
+                "id": "did:example:123#key-2",
+                "type": "Ed25519VerificationKey2020",
+                "controller": "did:example:123",
+                "publicKeyMultibase": "z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp"
+            }"##
+            .into(),
+            r##"{"id": "#key-3", "type": "JsonWebKey2020", "publicKeyJwk": {"kty": "OKP", "crv": "X25519", "x": "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE"}}"##.into(),
+        ]);
+        let auth = doc.keys_for_relationship(Relationship::Authentication);
+        assert_eq!(auth.len(), 3);
+        assert_eq!(auth[1].id, "did:example:123#key-2");
+        assert!(doc.is_authorized_for("#key-2", Relationship::Authentication));
+        assert!(doc.is_authorized_for("did:example:123#key-3", Relationship::Authentication));
+        assert!(!doc.is_authorized_for("#key-2", Relationship::AssertionMethod));
+        let (sign, _) = get_sign_and_crypto_keys(&doc);
+        // multicodec prefix is stripped from multibase key
+        assert_eq!(sign[1].public_key.len(), 32);
+        assert_eq!(sign[2].public_key, vec![1; 32]);
+    }
+
+    #[test]
+    fn find_referenced_key_agreement_test() {
+        let doc = did_key::resolve(DID)
+            .unwrap()
+            .get_did_document(did_key::CONFIG_LD_PUBLIC);
+        let reference = doc.key_agreement.as_ref().unwrap()[0].clone();
+        let fragment = &reference[reference.find('#').unwrap()..];
+        let by_id = doc.find_key_agreement(&reference).unwrap();
+        assert_eq!(by_id.id, reference);
+        assert_eq!(by_id.public_key().map(|k| k.len()), Some(32));
+        assert_eq!(doc.find_key_agreement(fragment), Some(by_id.clone()));
+        assert_eq!(
+            doc.find_key_agreement("X25519KeyAgreementKey2019"),
+            Some(by_id)
+        );
+        // substrings do not match any more
+        assert!(doc.find_key_agreement("X25519").is_none());
+        assert!(doc.find_key_agreement(&fragment[..10]).is_none());
+        let jose = did_key::resolve(DID)
+            .unwrap()
+            .get_did_document(did_key::CONFIG_JOSE_PUBLIC);
+        let ka = jose.find_key_agreement(fragment).unwrap();
+        assert!(ka.public_key_jwk.is_some());
+        assert_eq!(ka.public_key(), doc.find_public_key_for_curve("X25519"));
+    }
+
+    #[test]
+    fn find_embedded_key_agreement_test() {
+        let mut doc = document_with(KeyFormat::Base58("11".into()));
+        doc.key_agreement = Some(vec![r##"{
+            "id": "#key-agreement-1",
+            "type": "X25519KeyAgreementKey2020",
+            "controller": "did:example:123",
+            "publicKeyMultibase": "z6LSbysY2xFMRpGMhb7tFTLMpeuPRaqaWM1yECx2AtzE3KCc"
+        }"##
+        .into()]);
+        let ka = doc
+            .find_key_agreement("did:example:123#key-agreement-1")
+            .unwrap();
+        assert_eq!(ka.r#type, "X25519KeyAgreementKey2020");
+        assert_eq!(ka.public_key().map(|k| k.len()), Some(32));
+        assert_eq!(
+            doc.find_key_agreement("X25519KeyAgreementKey2020"),
+            Some(ka)
+        );
+        assert!(doc.find_key_agreement("#key-1").is_none());
+    }
+
+    #[test]
+    fn multibase_key_agreement_roundtrip_test() {
+        // raw key which starts with bytes of ed25519-pub multicodec prefix
+        let key = [&[0xed, 0x01][..], &[7; 30]].concat();
+        let mut vm = VerificationMethod {
+            id: "#key-1".into(),
+            key_type: "X25519KeyAgreementKey2019".into(),
+            controller: DID.into(),
+            public_key: Some(KeyFormat::Multibase(key.clone())),
+            private_key: None,
+        };
+        let ka = KeyAgreement::from(&vm);
+        assert!(ka
+            .public_key_multibase
+            .as_ref()
+            .unwrap()
+            .starts_with("z6LS"));
+        assert_eq!(ka.public_key(), Some(key.clone()));
+        vm.key_type = "Multikey".into();
+        let ka = KeyAgreement::from(&vm);
+        assert!(ka.public_key_multibase.is_none());
+        assert_eq!(ka.public_key(), Some(key));
+    }
+
+    #[test]
+    fn ec_jwk_public_key_test() {
+        for (curve, len) in [("secp256k1", 32), ("P-256", 32), ("P-384", 48)] {
+            let doc = document_with(ec_jwk(curve, &vec![1; len], &vec![2; len]));
+            let key = doc.find_public_key_for_curve(curve).unwrap();
+            assert_eq!(key.len(), 2 * len + 1);
+            assert_eq!(key[0], 0x04);
+            assert_eq!(&key[1..=len], vec![1; len].as_slice());
+            assert_eq!(&key[len + 1..], vec![2; len].as_slice());
+            let wrong = document_with(ec_jwk(curve, &vec![1; len - 1], &vec![2; len]));
+            assert!(wrong.find_public_key_for_curve(curve).is_none());
+        }
+        let unknown = document_with(ec_jwk("P-521", &[1; 66], &[2; 66]));
+        assert!(unknown.find_public_key_for_curve("P-521").is_none());
+    }
+}
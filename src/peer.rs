@@ -0,0 +1,534 @@
+use crate::{
+    document::{document_from_json, Service},
+    key::DidKeyResolver,
+    AsyncDdoResolver, DdoResolver, DidUrl, Document, Error, KeyFormat, ResolutionResult,
+    VerificationMethod,
+};
+use async_trait::async_trait;
+use base58::{FromBase58, ToBase58};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, MutexGuard},
+};
+
+/// `did:peer` resolver for numalgo 0, 2 and 4 as defined by
+///     https://identity.foundation/peer-did-method-spec/
+/// Short form of numalgo 4 DID can only be resolved after its long form
+///     was resolved by the same instance, as the document is not
+///     published anywhere else. Resolver remembers up to `capacity`
+///     (1000 by default) most recently learned long forms.
+/// Registered for `did:peer` in `ResolverRegistry::default()`.
+/// Available ONLY with `peer` feature
+///
+pub struct DidPeerResolver {
+    short_forms: Mutex<ShortForms>,
+}
+
+// Short form numalgo 4 DID -> encoded document of its long form,
+//  oldest ones are evicted once `capacity` is reached
+struct ShortForms {
+    capacity: usize,
+    encoded: HashMap<String, String>,
+    order: VecDeque<String>,
+}
+
+impl ShortForms {
+    fn insert(&mut self, short: String, encoded: String) {
+        if self.capacity == 0 || self.encoded.contains_key(&short) {
+            return;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.encoded.remove(&oldest);
+            }
+        }
+        self.order.push_back(short.clone());
+        self.encoded.insert(short, encoded);
+    }
+}
+
+impl DidPeerResolver {
+    pub fn new() -> Self {
+        Self::with_capacity(1000)
+    }
+
+    /// Resolver which remembers up to `capacity` short forms of numalgo 4
+    ///     DIDs, `0` disables resolution of short forms.
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            short_forms: Mutex::new(ShortForms {
+                capacity,
+                encoded: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+        }
+    }
+
+    // Lock is poisoned only if other thread panicked while holding it
+    fn short_forms(&self) -> Result<MutexGuard<'_, ShortForms>, Error> {
+        self.short_forms
+            .lock()
+            .map_err(|_| Error::InternalError("did:peer short forms lock is poisoned".into()))
+    }
+
+    // Decodes numalgo 4 DID into its long and short forms and document JSON
+    fn numalgo4_document(&self, did: &str) -> Result<((String, String), Value), Error> {
+        let id = &did["did:peer:4".len()..];
+        let (hash, encoded) = match id.find(':') {
+            Some(i) => (&id[..i], id[i + 1..].to_string()),
+            None => match self.short_forms()?.encoded.get(did) {
+                Some(encoded) => (id, encoded.clone()),
+                None => return Err(Error::NotFound(format!("unknown short form {}", did))),
+            },
+        };
+        if numalgo4_hash(&encoded) != hash {
+            return Err(Error::InvalidDid(format!("hash does not match: {}", did)));
+        }
+        let json = encoded
+            .strip_prefix('z')
+            .and_then(decode_base58)
+            .and_then(|bytes| bytes.strip_prefix(JSON_MULTICODEC).map(<[u8]>::to_vec))
+            .ok_or_else(|| Error::InvalidDid(format!("invalid encoded document: {}", did)))?;
+        let value: Value = serde_json::from_slice(&json)?;
+        let short = format!("did:peer:4{}", hash);
+        let long = format!("{}:{}", short, encoded);
+        self.short_forms()?.insert(short.clone(), encoded);
+        Ok(((long, short), value))
+    }
+
+    fn resolve_numalgo4(&self, did: &str) -> Result<(Document, String), Error> {
+        let ((long, short), mut value) = self.numalgo4_document(did)?;
+        let (id, equivalent) = if did == long {
+            (long, short)
+        } else {
+            (short, long)
+        };
+        match value.as_object_mut() {
+            Some(doc) => doc.insert("id".into(), Value::String(id.clone())),
+            None => return Err(Error::InvalidDocument("not a JSON object".into())),
+        };
+        let mut document = document_from_json(&value.to_string())?;
+        for vm in document.verification_method.iter_mut() {
+            if vm.controller.is_empty() || vm.controller.starts_with('#') {
+                vm.controller = id.clone();
+            }
+        }
+        Ok((document, equivalent))
+    }
+}
+
+impl Default for DidPeerResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DdoResolver for DidPeerResolver {
+    fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+        let did = did_url.parse::<DidUrl>()?.did();
+        match numalgo(&did)? {
+            ('0', key) => resolve_numalgo0(&did, key),
+            ('2', elements) => resolve_numalgo2(&did, elements),
+            (_, _) => Ok(self.resolve_numalgo4(&did)?.0),
+        }
+    }
+
+    /// Reports other form (long or short) of numalgo 4 DID as `equivalentId`.
+    ///
+    fn resolve_with_metadata(&self, did_url: &str) -> ResolutionResult {
+        let did = match did_url.parse::<DidUrl>() {
+            Ok(url) => url.did(),
+            Err(e) => return e.into(),
+        };
+        match numalgo(&did) {
+            Ok(('4', _)) => match self.resolve_numalgo4(&did) {
+                Ok((document, equivalent)) => {
+                    let mut result = ResolutionResult::from_document(document);
+                    result.did_document_metadata.equivalent_id = Some(vec![equivalent]);
+                    result
+                }
+                Err(e) => e.into(),
+            },
+            _ => self.resolve(did_url).into(),
+        }
+    }
+
+    /// Numalgo 0 DIDs have no services.
+    ///
+    fn services(&self, did_url: &str) -> Result<Vec<Service>, Error> {
+        let did = did_url.parse::<DidUrl>()?.did();
+        match numalgo(&did)? {
+            ('0', _) => Ok(vec![]),
+            ('2', elements) => numalgo2_services(elements),
+            (_, _) => {
+                let value = self.numalgo4_document(&did)?.1;
+                match value.get("service") {
+                    Some(services) => Ok(serde_json::from_value(services.clone())?),
+                    None => Ok(vec![]),
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncDdoResolver for DidPeerResolver {
+    async fn resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+        self.resolve(did_url)
+    }
+
+    async fn resolve_with_metadata_async(&self, did_url: &str) -> ResolutionResult {
+        self.resolve_with_metadata(did_url)
+    }
+}
+
+/// Encodes numalgo 4 DID for document `json` (without `id`).
+/// Output is `(long_form, short_form)`.
+///
+pub fn numalgo4_did(json: &Value) -> (String, String) {
+    let encoded = format!(
+        "z{}",
+        [JSON_MULTICODEC, json.to_string().as_bytes()]
+            .concat()
+            .to_base58()
+    );
+    let short = format!("did:peer:4{}", numalgo4_hash(&encoded));
+    (format!("{}:{}", short, encoded), short)
+}
+
+// multicodec prefix of JSON (0x0200)
+const JSON_MULTICODEC: &[u8] = &[0x80, 0x04];
+
+// Decodes base58btc of any length, as `base58` crate can not decode
+//  more than 132 bytes, which is not enough for encoded documents
+fn decode_base58(encoded: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    // little endian digits of the decoded number
+    let mut bytes: Vec<u8> = vec![];
+    for c in encoded.bytes() {
+        let mut carry = ALPHABET.iter().position(|a| *a == c)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    // leading '1's are leading zero bytes
+    let zeros = encoded.bytes().take_while(|c| *c == b'1').count();
+    bytes.resize(bytes.len() + zeros, 0);
+    bytes.reverse();
+    Some(bytes)
+}
+
+// base58btc multibase of sha2-256 multihash
+fn numalgo4_hash(encoded: &str) -> String {
+    let digest = Sha256::digest(encoded.as_bytes());
+    format!("z{}", [&[0x12, 0x20], &digest[..]].concat().to_base58())
+}
+
+// Splits `did` into numalgo and the rest of method specific id
+fn numalgo(did: &str) -> Result<(char, &str), Error> {
+    let id = did
+        .strip_prefix("did:peer:")
+        .ok_or_else(|| Error::InvalidDid(format!("not a did:peer: {}", did)))?;
+    match id.chars().next() {
+        Some(n @ '0') | Some(n @ '2') | Some(n @ '4') => Ok((n, &id[1..])),
+        Some('1') | Some('3') => Err(Error::MethodNotSupported(format!(
+            "did:peer numalgo {}",
+            &id[..1]
+        ))),
+        _ => Err(Error::InvalidDid(format!("invalid numalgo: {}", did))),
+    }
+}
+
+// Numalgo 0 is inception key encoded the same way as `did:key`
+fn resolve_numalgo0(did: &str, key: &str) -> Result<Document, Error> {
+    let did_key = format!("did:key:{}", key);
+    let mut document = DidKeyResolver {}.resolve(&did_key).map_err(|e| match e {
+        Error::InvalidDid(reason) => Error::InvalidDid(reason.replace(&did_key, did)),
+        e => e,
+    })?;
+    let rename = |s: &mut String| *s = s.replace(&did_key, did);
+    rename(&mut document.id);
+    for vm in document.verification_method.iter_mut() {
+        rename(&mut vm.id);
+        rename(&mut vm.controller);
+    }
+    for relationship in [
+        &mut document.authentication,
+        &mut document.assertion_method,
+        &mut document.capability_delegation,
+        &mut document.capability_invocation,
+        &mut document.key_agreement,
+    ] {
+        relationship.iter_mut().flatten().for_each(rename);
+    }
+    Ok(document)
+}
+
+// Numalgo 2 encodes keys, each prefixed with its purpose, and services
+fn resolve_numalgo2(did: &str, elements: &str) -> Result<Document, Error> {
+    let mut document = Document {
+        context: "https://www.w3.org/ns/did/v1".into(),
+        id: did.into(),
+        assertion_method: None,
+        authentication: None,
+        capability_delegation: None,
+        capability_invocation: None,
+        key_agreement: None,
+        verification_method: vec![],
+    };
+    for element in elements.split('.').filter(|e| !e.is_empty()) {
+        let (purpose, value) = element.split_at(1);
+        let relationship = match purpose {
+            "A" => &mut document.assertion_method,
+            "E" => &mut document.key_agreement,
+            "V" => &mut document.authentication,
+            "I" => &mut document.capability_invocation,
+            "D" => &mut document.capability_delegation,
+            "S" => continue,
+            _ => return Err(Error::InvalidDid(format!("invalid purpose code: {}", did))),
+        };
+        let (key_type, public_key) = decode_multikey(value)
+            .ok_or_else(|| Error::InvalidDid(format!("invalid key {}: {}", value, did)))?;
+        let id = format!("{}#key-{}", did, document.verification_method.len() + 1);
+        relationship.get_or_insert_with(Vec::new).push(id.clone());
+        document.verification_method.push(VerificationMethod {
+            id,
+            key_type: key_type.into(),
+            controller: did.into(),
+            public_key: Some(KeyFormat::Multibase(public_key)),
+            private_key: None,
+        });
+    }
+    Ok(document)
+}
+
+fn numalgo2_services(elements: &str) -> Result<Vec<Service>, Error> {
+    elements
+        .split('.')
+        .filter_map(|e| e.strip_prefix('S'))
+        .enumerate()
+        .map(|(i, encoded)| {
+            let json: Value = serde_json::from_slice(&base64_url::decode(encoded)?)?;
+            let mut service = expand_abbreviations(json);
+            if let Some(service) = service.as_object_mut() {
+                if !service.contains_key("id") {
+                    let id = match i {
+                        0 => "#service".to_string(),
+                        i => format!("#service-{}", i),
+                    };
+                    service.insert("id".into(), Value::String(id));
+                }
+            }
+            Ok(serde_json::from_value(service)?)
+        })
+        .collect()
+}
+
+// Expands service abbreviations of numalgo 2:
+//  https://identity.foundation/peer-did-method-spec/#service-abbreviation
+fn expand_abbreviations(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(k, v)| {
+                    let key = match k.as_str() {
+                        "t" => "type",
+                        "s" => "serviceEndpoint",
+                        "r" => "routingKeys",
+                        "a" => "accept",
+                        other => other,
+                    };
+                    (key.to_string(), expand_abbreviations(v))
+                })
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(values) => {
+            Value::Array(values.into_iter().map(expand_abbreviations).collect())
+        }
+        Value::String(s) if s == "dm" => Value::String("DIDCommMessaging".into()),
+        other => other,
+    }
+}
+
+// Decodes base58btc multibase multicodec key into type of
+//  verification method and raw key bytes
+fn decode_multikey(multibase: &str) -> Option<(&'static str, Vec<u8>)> {
+    let bytes = multibase.strip_prefix('z')?.from_base58().ok()?;
+    let (key_type, len) = match bytes.get(..2)? {
+        [0xed, 0x01] => ("Ed25519VerificationKey2020", 32),
+        [0xec, 0x01] => ("X25519KeyAgreementKey2020", 32),
+        [0xe7, 0x01] => ("EcdsaSecp256k1VerificationKey2019", 33),
+        [0x80, 0x24] => ("P256Key2021", 33),
+        _ => return None,
+    };
+    if bytes.len() == len + 2 {
+        Some((key_type, bytes[2..].to_vec()))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod did_peer_tests {
+    use super::*;
+    use crate::{registry::ResolverRegistry, DdoParser, Relationship};
+    use serde_json::json;
+
+    const NUMALGO0: &str = "did:peer:0z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+    const NUMALGO2: &str = "did:peer:2.Ez6LSbysY2xFMRpGMhb7tFTLMpeuPRaqaWM1yECx2AtzE3KCc.Vz6MkqRYqQiSgvZQdnBytw86Qbs2ZWUkGv22od935YF4s8M7V.Vz6MkgoLTnTypo3tDRwCkZXSccTPHRLhF4ZnjhueYAFpEX6vg.SeyJ0IjoiZG0iLCJzIjoiaHR0cHM6Ly9leGFtcGxlLmNvbS9lbmRwb2ludCIsInIiOlsiZGlkOmV4YW1wbGU6c29tZW1lZGlhdG9yI3NvbWVrZXkiXSwiYSI6WyJkaWRjb21tL3YyIiwiZGlkY29tbS9haXAyO2Vudj1yZmM1ODciXX0";
+
+    fn numalgo4_input() -> Value {
+        json!({
+            "@context": ["https://www.w3.org/ns/did/v1"],
+            "verificationMethod": [{
+                "id": "#key-1",
+                "type": "Ed25519VerificationKey2020",
+                "publicKeyMultibase": "z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp"
+            }],
+            "authentication": ["#key-1"],
+            "service": [{
+                "id": "#didcomm",
+                "type": "DIDCommMessaging",
+                "serviceEndpoint": "https://example.com/endpoint"
+            }]
+        })
+    }
+
+    #[test]
+    fn resolve_numalgo0_test() {
+        let doc = DidPeerResolver::new().resolve(NUMALGO0).unwrap();
+        assert_eq!(doc.id, NUMALGO0);
+        assert!(doc
+            .verification_method
+            .iter()
+            .all(|vm| vm.id.starts_with(NUMALGO0) && vm.controller == NUMALGO0));
+        assert_eq!(
+            doc.keys_for_relationship(Relationship::Authentication)
+                .len(),
+            1
+        );
+        assert!(doc
+            .find_key_agreement("X25519KeyAgreementKey2019")
+            .is_some());
+        assert!(DidPeerResolver::new().resolve("did:peer:0z6Mk").is_err());
+    }
+
+    #[test]
+    fn resolve_numalgo2_test() {
+        let resolver = DidPeerResolver::new();
+        let doc = resolver.resolve(NUMALGO2).unwrap();
+        assert_eq!(doc.verification_method.len(), 3);
+        let agreement = doc.keys_for_relationship(Relationship::KeyAgreement);
+        assert_eq!(agreement.len(), 1);
+        assert_eq!(agreement[0].id, format!("{}#key-1", NUMALGO2));
+        assert_eq!(agreement[0].key_type, "X25519KeyAgreementKey2020");
+        assert_eq!(
+            doc.keys_for_relationship(Relationship::Authentication)
+                .len(),
+            2
+        );
+        assert_eq!(
+            doc.find_public_key_for_curve("Ed25519").map(|k| k.len()),
+            Some(32)
+        );
+        let services = resolver.services(NUMALGO2).unwrap();
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].id, "#service");
+        assert_eq!(services[0].service_type, "DIDCommMessaging");
+        assert_eq!(
+            services[0].service_endpoint,
+            json!("https://example.com/endpoint")
+        );
+        assert_eq!(
+            services[0].routing_keys,
+            vec!["did:example:somemediator#somekey"]
+        );
+        assert_eq!(services[0].accept.len(), 2);
+        assert!(resolver.resolve("did:peer:2.Xz6Mk").is_err());
+    }
+
+    #[test]
+    fn resolve_numalgo4_test() {
+        let resolver = DidPeerResolver::new();
+        let (long, short) = numalgo4_did(&numalgo4_input());
+        // short form is unknown until long form is resolved
+        let res = resolver.resolve_with_metadata(&short);
+        assert_eq!(res.did_resolution_metadata.error, Some("notFound".into()));
+        let res = resolver.resolve_with_metadata(&long);
+        let doc = res.did_document.unwrap();
+        assert_eq!(doc.id, long);
+        assert_eq!(
+            res.did_document_metadata.equivalent_id,
+            Some(vec![short.clone()])
+        );
+        assert!(doc.is_authorized_for("#key-1", Relationship::Authentication));
+        assert_eq!(doc.verification_method[0].controller, long);
+        let res = resolver.resolve_with_metadata(&short);
+        assert_eq!(res.did_document.unwrap().id, short);
+        assert_eq!(
+            res.did_document_metadata.equivalent_id,
+            Some(vec![long.clone()])
+        );
+        let services = resolver.services(&short).unwrap();
+        assert_eq!(services[0].id, "#didcomm");
+    }
+
+    #[test]
+    fn short_forms_capacity_test() {
+        let resolver = DidPeerResolver::with_capacity(1);
+        let (long, short) = numalgo4_did(&numalgo4_input());
+        let (other_long, other_short) = numalgo4_did(&json!({}));
+        assert!(resolver.resolve(&long).is_ok());
+        assert!(resolver.resolve(&short).is_ok());
+        assert!(resolver.resolve(&other_long).is_ok());
+        assert!(resolver.resolve(&other_short).is_ok());
+        // evicted by the other one
+        assert!(matches!(resolver.resolve(&short), Err(Error::NotFound(_))));
+        let disabled = DidPeerResolver::with_capacity(0);
+        assert!(disabled.resolve(&long).is_ok());
+        assert!(matches!(disabled.resolve(&short), Err(Error::NotFound(_))));
+    }
+
+    #[test]
+    fn reject_numalgo4_hash_mismatch_test() {
+        let (long, _) = numalgo4_did(&numalgo4_input());
+        let (_, other) = numalgo4_did(&json!({}));
+        let tampered = format!("{}{}", other, &long[long.rfind(':').unwrap()..]);
+        assert!(matches!(
+            DidPeerResolver::new().resolve(&tampered),
+            Err(Error::InvalidDid(_))
+        ));
+    }
+
+    #[test]
+    fn decode_base58_test() {
+        let short = "z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+        assert_eq!(decode_base58(short), short.from_base58().ok());
+        assert_eq!(decode_base58("1112"), Some(vec![0, 0, 0, 1]));
+        let long: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode_base58(&long.to_base58()), Some(long));
+        assert_eq!(decode_base58("0OIl"), None);
+    }
+
+    #[test]
+    fn unsupported_numalgo_test() {
+        let registry = ResolverRegistry::default();
+        let res = registry
+            .resolve_with_metadata("did:peer:1zQmZMygzYqNwU6Uhmewx5Xepf2VLp5S4HLSwwgf2aiKZuwa");
+        assert_eq!(
+            res.did_resolution_metadata.error,
+            Some("methodNotSupported".into())
+        );
+        assert!(registry.resolve(NUMALGO2).is_some());
+    }
+}
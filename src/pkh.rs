@@ -0,0 +1,184 @@
+use crate::{
+    AsyncDdoResolver, DdoResolver, DidUrl, Document, Error, KeyFormat, VerificationMethod,
+};
+use async_trait::async_trait;
+use base58::FromBase58;
+
+/// Unit struct which has implementation of `DdoResolver` trait for
+///     `did:pkh` document resolver:
+///     https://github.com/w3c-ccg/did-pkh/blob/main/did-pkh-method-draft.md
+/// Documents are built offline from CAIP-10 account id of the DID.
+///     Supported namespaces are `eip155` (`EcdsaSecp256k1RecoveryMethod2020`
+///     without public key, as only address is known) and `solana`
+///     (`Ed25519VerificationKey2018` with the address as public key).
+/// `VerificationMethod` has no `blockchainAccountId` property, use
+///     `blockchain_account_id()` to get it instead.
+/// Available ONLY with `didpkh` feature
+///
+pub struct DidPkhResolver;
+
+impl DdoResolver for DidPkhResolver {
+    fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+        let did = did_url.parse::<DidUrl>()?.did();
+        let account = blockchain_account_id(&did)?;
+        let (id, key_type, public_key) = match account.split(':').collect::<Vec<&str>>()[..] {
+            ["eip155", _, _] => (
+                format!("{}#blockchainAccountId", did),
+                "EcdsaSecp256k1RecoveryMethod2020",
+                None,
+            ),
+            ["solana", _, address] => (
+                format!("{}#controller", did),
+                "Ed25519VerificationKey2018",
+                Some(KeyFormat::Base58(address.into())),
+            ),
+            _ => unreachable!("validated by blockchain_account_id()"),
+        };
+        Ok(Document {
+            context: "https://www.w3.org/ns/did/v1".into(),
+            id: did.clone(),
+            assertion_method: Some(vec![id.clone()]),
+            authentication: Some(vec![id.clone()]),
+            capability_delegation: None,
+            capability_invocation: None,
+            key_agreement: None,
+            verification_method: vec![VerificationMethod {
+                id,
+                key_type: key_type.into(),
+                controller: did,
+                public_key,
+                private_key: None,
+            }],
+        })
+    }
+}
+
+#[async_trait]
+impl AsyncDdoResolver for DidPkhResolver {
+    async fn resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+        self.resolve(did_url)
+    }
+}
+
+/// CAIP-10 account id (`<namespace>:<reference>:<address>`) of `did:pkh`
+///     DID or of verification method controlled by it.
+/// Fails for malformed account ids or unsupported namespaces.
+///
+pub fn blockchain_account_id(did: &str) -> Result<String, Error> {
+    let url: DidUrl = did.parse()?;
+    if url.method != "pkh" {
+        return Err(Error::InvalidDid(format!("not a did:pkh: {}", did)));
+    }
+    let invalid = |reason: &str| Error::InvalidDid(format!("{}: {}", reason, did));
+    let account = url.method_specific_id;
+    let (namespace, reference, address) = match account.split(':').collect::<Vec<&str>>()[..] {
+        [namespace, reference, address] => (namespace, reference, address),
+        _ => return Err(invalid("not a CAIP-10 account id")),
+    };
+    let caip_chars = |s: &str, extra: &[char]| {
+        s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || extra.contains(&c))
+    };
+    if !(3..=8).contains(&namespace.len())
+        || !caip_chars(namespace, &[])
+        || !(1..=32).contains(&reference.len())
+        || !caip_chars(reference, &['_'])
+        || !(1..=128).contains(&address.len())
+        || !caip_chars(address, &['.', '%'])
+    {
+        return Err(invalid("not a CAIP-10 account id"));
+    }
+    match namespace {
+        "eip155" => {
+            let hex = address
+                .strip_prefix("0x")
+                .ok_or_else(|| invalid("address must start with 0x"))?;
+            if hex.len() != 40
+                || !hex.chars().all(|c| c.is_ascii_hexdigit())
+                || !reference.chars().all(|c| c.is_ascii_digit())
+            {
+                return Err(invalid("invalid eip155 account"));
+            }
+        }
+        "solana" => match address.from_base58() {
+            Ok(key) if key.len() == 32 => {}
+            _ => return Err(invalid("invalid solana address")),
+        },
+        _ => {
+            return Err(Error::MethodNotSupported(format!(
+                "did:pkh namespace {}",
+                namespace
+            )))
+        }
+    }
+    Ok(account)
+}
+
+#[cfg(test)]
+mod did_pkh_tests {
+    use super::*;
+    use crate::{resolve_with_metadata, try_resolve_any, DdoParser, Relationship};
+
+    const ETH: &str = "did:pkh:eip155:1:0xb9c5714089478a327f09197987f16f9e5d936e8a";
+    const SOL: &str =
+        "did:pkh:solana:4sGjMW1sUnHzSxGspuhpqLDx6wiyjNtZ:CKg5d12Jhpej1JqtmxLJgaFqqeYjxgPqToJ4LBdvG9Ev";
+
+    #[test]
+    fn resolve_eip155_test() {
+        let doc = try_resolve_any(ETH).unwrap();
+        assert_eq!(doc.id, ETH);
+        let vm = &doc.verification_method[0];
+        assert_eq!(vm.id, format!("{}#blockchainAccountId", ETH));
+        assert_eq!(vm.key_type, "EcdsaSecp256k1RecoveryMethod2020");
+        assert!(vm.public_key.is_none());
+        assert_eq!(
+            blockchain_account_id(&vm.controller).unwrap(),
+            "eip155:1:0xb9c5714089478a327f09197987f16f9e5d936e8a"
+        );
+        assert!(doc.is_authorized_for("#blockchainAccountId", Relationship::Authentication));
+        assert!(!doc.is_authorized_for("#blockchainAccountId", Relationship::KeyAgreement));
+    }
+
+    #[test]
+    fn resolve_solana_test() {
+        let doc = DidPkhResolver
+            .resolve(&format!("{}#controller", SOL))
+            .unwrap();
+        assert_eq!(doc.id, SOL);
+        assert_eq!(
+            doc.find_public_key_for_curve("Ed25519").map(|k| k.len()),
+            Some(32)
+        );
+        assert_eq!(
+            doc.find_public_key_controller_for_curve("Ed25519"),
+            Some(SOL.to_string())
+        );
+        let auth = doc.keys_for_relationship(Relationship::AssertionMethod);
+        assert_eq!(auth[0].id, format!("{}#controller", SOL));
+    }
+
+    #[test]
+    fn reject_invalid_accounts_test() {
+        for did in [
+            "did:pkh:eip155:1",
+            "did:pkh:eip155:1:b9c5714089478a327f09197987f16f9e5d936e8a",
+            "did:pkh:eip155:1:0xb9c5714089478a327f09197987f16f9e5d936e8",
+            "did:pkh:eip155:one:0xb9c5714089478a327f09197987f16f9e5d936e8a",
+            "did:pkh:solana:4sGjMW1sUnHzSxGspuhpqLDx6wiyjNtZ:CKg5d12Jhpej1Jqtmx",
+            "did:pkh:ab:1:0xb9c5714089478a327f09197987f16f9e5d936e8a",
+        ] {
+            assert!(
+                matches!(DidPkhResolver.resolve(did), Err(Error::InvalidDid(_))),
+                "{} should be rejected",
+                did
+            );
+        }
+        let res = resolve_with_metadata(
+            "did:pkh:bip122:000000000019d6689c085ae165831e93:128Lkh3S7CkDTBZ8W7BbpsN3YYizJMp8p6",
+        );
+        assert_eq!(
+            res.did_resolution_metadata.error,
+            Some("methodNotSupported".into())
+        );
+    }
+}
@@ -0,0 +1,475 @@
+use crate::{
+    dereference::{dereference_with, DereferencingResult},
+    error::Error,
+    AsyncDdoResolver, DdoResolver, DidUrl, Document, ResolutionResult, Service,
+};
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(feature = "jolo")]
+use crate::jolo::{JoloConfig, JoloResolver};
+#[cfg(feature = "didjwk")]
+use crate::jwk::DidJwkResolver;
+#[cfg(feature = "keriox")]
+use crate::keri::KerlQueryResolver;
+#[cfg(feature = "didkey")]
+use crate::key::DidKeyResolver;
+#[cfg(feature = "peer")]
+use crate::peer::DidPeerResolver;
+#[cfg(feature = "didpkh")]
+use crate::pkh::DidPkhResolver;
+#[cfg(feature = "didweb")]
+use crate::web::DidWebResolver;
+
+/// Set of resolvers keyed by DID method name, which dispatches
+///     DID urls to resolver of their method.
+/// `ResolverRegistry::default()` has all built in resolvers of enabled
+///     features registered, `ResolverRegistry::new()` is empty.
+/// Applications can register their own resolvers (including private
+///     methods) or replace built in ones with `register()`.
+///
+/// # Example
+/// ```
+/// use ddoresolver_rs::{registry::ResolverRegistry, DdoResolver, Document, error::Error};
+///
+/// struct ExampleResolver;
+///
+/// impl DdoResolver for ExampleResolver {
+///     fn resolve(&self, _did_url: &str) -> Result<Document, Error> {
+///         Err(Error::DidResolutionFailed)
+///     }
+/// }
+///
+/// let mut registry = ResolverRegistry::default();
+/// registry.register("example", Box::new(ExampleResolver));
+/// assert!(registry.resolve("did:example:123").is_none());
+/// ```
+///
+pub struct ResolverRegistry {
+    resolvers: HashMap<String, Arc<dyn DdoResolver + Send + Sync>>,
+    async_resolvers: HashMap<String, Box<dyn AsyncDdoResolver + Send + Sync>>,
+}
+
+impl ResolverRegistry {
+    /// Empty registry without any resolvers.
+    ///
+    pub fn new() -> Self {
+        Self {
+            resolvers: HashMap::new(),
+            async_resolvers: HashMap::new(),
+        }
+    }
+
+    /// Registers `resolver` for `method` (name without "did:" prefix),
+    ///     replacing previously registered one, if any. Asynchronous
+    ///     resolver of `method` is removed as well, so `*_async` methods
+    ///     fall back to `resolver` until `register_async()` is called.
+    ///
+    pub fn register(
+        &mut self,
+        method: &str,
+        resolver: Box<dyn DdoResolver + Send + Sync>,
+    ) -> &mut Self {
+        self.async_resolvers.remove(method);
+        self.resolvers.insert(method.into(), Arc::from(resolver));
+        self
+    }
+
+    /// Registers asynchronous `resolver` for `method`, which is used
+    ///     by `*_async` methods. Methods without asynchronous resolver
+    ///     fall back to the one registered with `register()`, which is
+    ///     run on blocking thread pool when called within tokio runtime.
+    ///
+    pub fn register_async(
+        &mut self,
+        method: &str,
+        resolver: Box<dyn AsyncDdoResolver + Send + Sync>,
+    ) -> &mut Self {
+        self.async_resolvers.insert(method.into(), resolver);
+        self
+    }
+
+    /// Names of all methods which can be resolved by `try_resolve()`.
+    /// Methods registered with `register_async()` only are not listed,
+    ///     as they can be resolved by `*_async` methods only.
+    ///
+    pub fn methods(&self) -> Vec<&str> {
+        let mut methods: Vec<&str> = self.resolvers.keys().map(String::as_str).collect();
+        methods.sort_unstable();
+        methods
+    }
+
+    /// Resolves `did_url` with resolver registered for its method.
+    /// Resolver is given full `did_url` with path, query and fragment.
+    ///
+    pub fn try_resolve(&self, did_url: &str) -> Result<Document, Error> {
+        self.resolver_for(did_url)?.resolve(did_url)
+    }
+
+    /// Same as `try_resolve()`, but returns `None` instead of error.
+    ///
+    pub fn resolve(&self, did_url: &str) -> Option<Document> {
+        self.try_resolve(did_url).ok()
+    }
+
+    /// Same as `try_resolve()`, but outputs full `ResolutionResult`.
+    ///
+    pub fn resolve_with_metadata(&self, did_url: &str) -> ResolutionResult {
+        match self.resolver_for(did_url) {
+            Ok(resolver) => resolver.resolve_with_metadata(did_url),
+            Err(e) => e.into(),
+        }
+    }
+
+    /// Services of `did_url` from resolver registered for its method.
+    ///
+    pub fn services(&self, did_url: &str) -> Result<Vec<Service>, Error> {
+        self.resolver_for(did_url)?.services(did_url)
+    }
+
+    /// Dereferences `did_url` into the resource it points at.
+    /// Resolves the document first (with all query parameters, so
+    ///     `?kerl=` and such are passed to the resolver) and then selects
+    ///     verification method (listed or embedded in a verification
+    ///     relationship) or service by url fragment. Fragment is matched
+    ///     against either absolute (`did:...#id`) or relative (`#id`) ids.
+    /// Will never fail, errors are reported in `dereferencing_metadata.error`.
+    ///
+    pub fn dereference(&self, did_url: &str) -> DereferencingResult {
+        dereference_with(self, did_url)
+    }
+
+    /// Asynchronous version of `try_resolve()`.
+    ///
+    pub async fn try_resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+        let method = method_of(did_url)?;
+        match self.async_resolvers.get(&method) {
+            Some(resolver) => resolver.resolve_async(did_url).await,
+            None => {
+                let (resolver, did_url) =
+                    (self.resolver_for(did_url)?.clone(), did_url.to_string());
+                run_blocking(move || resolver.resolve(&did_url)).await?
+            }
+        }
+    }
+
+    /// Asynchronous version of `resolve_with_metadata()`.
+    ///
+    pub async fn resolve_with_metadata_async(&self, did_url: &str) -> ResolutionResult {
+        let method = match method_of(did_url) {
+            Ok(method) => method,
+            Err(e) => return e.into(),
+        };
+        match self.async_resolvers.get(&method) {
+            Some(resolver) => resolver.resolve_with_metadata_async(did_url).await,
+            None => {
+                let resolver = match self.resolver_for(did_url) {
+                    Ok(resolver) => resolver.clone(),
+                    Err(e) => return e.into(),
+                };
+                let did_url = did_url.to_string();
+                run_blocking(move || resolver.resolve_with_metadata(&did_url))
+                    .await
+                    .unwrap_or_else(ResolutionResult::from)
+            }
+        }
+    }
+
+    /// Default registry with `JoloResolver` configured by `config`
+    ///     instead of the one from `JoloConfig::from_env()`.
+    /// Fails if configured endpoints can not be parsed.
+    ///
+    #[cfg(feature = "jolo")]
+    pub fn with_jolo(config: &JoloConfig) -> Result<Self, Error> {
+        let mut registry = Self::default();
+        registry.register_jolo(Ok(JoloResolver::from_config(config)?));
+        Ok(registry)
+    }
+
+    // Registers `jolo` resolver, or one reporting why it could not be
+    //  created, so misconfiguration is not hidden as unsupported method
+    #[cfg(feature = "jolo")]
+    fn register_jolo(&mut self, jolo: Result<JoloResolver, Error>) -> &mut Self {
+        match jolo {
+            Ok(jolo) => {
+                let jolo = Arc::new(jolo);
+                self.register("jolo", Box::new(jolo.clone()))
+                    .register_async("jolo", Box::new(jolo))
+            }
+            Err(e) => self.register("jolo", Box::new(UnconfiguredResolver(e.to_string()))),
+        }
+    }
+
+    fn resolver_for(&self, did_url: &str) -> Result<&Arc<dyn DdoResolver + Send + Sync>, Error> {
+        let method = method_of(did_url)?;
+        match self.resolvers.get(&method) {
+            Some(resolver) => Ok(resolver),
+            None => Err(Error::MethodNotSupported(method)),
+        }
+    }
+}
+
+impl Default for ResolverRegistry {
+    /// Registry with resolvers of all enabled features.
+    /// `did:jolo` resolver is configured from file at path in `JOLO_CONFIG`
+    ///     environment variable, without it `did:jolo` resolution fails
+    ///     with `Error::ConfigOpenError`. Use `ResolverRegistry::with_jolo()`
+    ///     to configure it explicitly.
+    ///
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::new();
+        #[cfg(feature = "didkey")]
+        registry
+            .register("key", Box::new(DidKeyResolver {}))
+            .register_async("key", Box::new(DidKeyResolver {}));
+        #[cfg(feature = "keriox")]
+        registry
+            .register("keri", Box::new(KerlQueryResolver))
+            .register_async("keri", Box::new(KerlQueryResolver));
+        #[cfg(feature = "didweb")]
+        registry.register("web", Box::new(DidWebResolver::new()));
+        #[cfg(feature = "peer")]
+        registry.register("peer", Box::new(DidPeerResolver::new()));
+        #[cfg(feature = "didjwk")]
+        registry
+            .register("jwk", Box::new(DidJwkResolver))
+            .register_async("jwk", Box::new(DidJwkResolver));
+        #[cfg(feature = "didpkh")]
+        registry
+            .register("pkh", Box::new(DidPkhResolver))
+            .register_async("pkh", Box::new(DidPkhResolver));
+        // resolution fails with config error if it is not set or can not be loaded
+        #[cfg(feature = "jolo")]
+        registry.register_jolo(
+            JoloConfig::from_env().and_then(|config| JoloResolver::from_config(&config)),
+        );
+        registry
+    }
+}
+
+// Resolver of method, which could not be configured, failing with
+//  the reason it could not be
+#[cfg(feature = "jolo")]
+struct UnconfiguredResolver(String);
+
+#[cfg(feature = "jolo")]
+impl DdoResolver for UnconfiguredResolver {
+    fn resolve(&self, _did_url: &str) -> Result<Document, Error> {
+        Err(Error::ConfigOpenError(self.0.clone()))
+    }
+}
+
+// Parses method name out of `did_url`
+fn method_of(did_url: &str) -> Result<String, Error> {
+    Ok(did_url.parse::<DidUrl>()?.method)
+}
+
+// Runs blocking `f` on tokio blocking thread pool, so it does not stall
+//  executor, or in place if not within tokio runtime.
+async fn run_blocking<T, F>(f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => handle
+            .spawn_blocking(f)
+            .await
+            .map_err(|e| match e.try_into_panic() {
+                Ok(panic) => std::panic::resume_unwind(panic),
+                Err(_) => Error::DidResolutionFailed,
+            }),
+        Err(_) => Ok(f()),
+    }
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use super::*;
+
+    struct ExampleResolver;
+
+    impl DdoResolver for ExampleResolver {
+        fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+            Ok(Document {
+                context: "https://www.w3.org/ns/did/v1".into(),
+                id: did_url.into(),
+                assertion_method: None,
+                authentication: None,
+                capability_delegation: None,
+                capability_invocation: None,
+                key_agreement: None,
+                verification_method: vec![],
+            })
+        }
+    }
+
+    #[test]
+    fn register_private_method_test() {
+        let mut registry = ResolverRegistry::new();
+        assert!(registry.methods().is_empty());
+        assert!(registry.try_resolve("did:example:123").is_err());
+        registry.register("example", Box::new(ExampleResolver));
+        assert_eq!(registry.methods(), vec!["example"]);
+        let doc = registry.try_resolve("did:example:123").unwrap();
+        assert_eq!(doc.id, "did:example:123");
+        assert!(registry.resolve("did:other:123").is_none());
+    }
+
+    #[tokio::test]
+    async fn async_falls_back_to_sync_resolver_test() {
+        let mut registry = ResolverRegistry::new();
+        registry.register("example", Box::new(ExampleResolver));
+        let res = registry
+            .resolve_with_metadata_async("did:example:123")
+            .await;
+        assert!(res.is_ok());
+    }
+
+    #[tokio::test]
+    async fn async_fallback_runs_off_executor_test() {
+        struct ThreadResolver(std::thread::ThreadId);
+
+        impl DdoResolver for ThreadResolver {
+            fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+                assert_ne!(std::thread::current().id(), self.0);
+                ExampleResolver.resolve(did_url)
+            }
+        }
+
+        let mut registry = ResolverRegistry::new();
+        registry.register(
+            "example",
+            Box::new(ThreadResolver(std::thread::current().id())),
+        );
+        assert!(registry.try_resolve_async("did:example:123").await.is_ok());
+        let res = registry
+            .resolve_with_metadata_async("did:example:123")
+            .await;
+        assert!(res.is_ok());
+    }
+
+    #[tokio::test]
+    async fn shared_resolver_test() {
+        struct ExampleAsyncResolver;
+
+        #[async_trait::async_trait]
+        impl AsyncDdoResolver for ExampleAsyncResolver {
+            async fn resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+                ExampleResolver.resolve(did_url)
+            }
+        }
+
+        impl DdoResolver for ExampleAsyncResolver {
+            fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+                ExampleResolver.resolve(did_url)
+            }
+        }
+
+        let shared = std::sync::Arc::new(ExampleAsyncResolver);
+        let mut registry = ResolverRegistry::new();
+        registry
+            .register("example", Box::new(shared.clone()))
+            .register_async("example", Box::new(shared.clone()));
+        assert!(registry.resolve("did:example:123").is_some());
+        assert!(registry.try_resolve_async("did:example:123").await.is_ok());
+        assert_eq!(std::sync::Arc::strong_count(&shared), 3);
+    }
+
+    #[tokio::test]
+    async fn override_built_in_method_test() {
+        let mut registry = ResolverRegistry::default();
+        registry.register("key", Box::new(ExampleResolver));
+        let did = "did:key:z6MkpTHR8VNsBxYAAWHut2Geadd9jSwuBV8xRoAnwWsdvktH";
+        // built in resolver would have the key as verification method
+        let doc = registry.try_resolve(did).unwrap();
+        assert!(doc.verification_method.is_empty());
+        let doc = registry.try_resolve_async(did).await.unwrap();
+        assert!(doc.verification_method.is_empty());
+        let res = registry.resolve_with_metadata_async(did).await;
+        assert!(res.did_document.unwrap().verification_method.is_empty());
+        assert!(registry.methods().contains(&"key"));
+    }
+
+    #[tokio::test]
+    async fn async_only_method_test() {
+        struct ExampleAsyncResolver;
+
+        #[async_trait::async_trait]
+        impl AsyncDdoResolver for ExampleAsyncResolver {
+            async fn resolve_async(&self, did_url: &str) -> Result<Document, Error> {
+                ExampleResolver.resolve(did_url)
+            }
+        }
+
+        let mut registry = ResolverRegistry::new();
+        registry.register_async("example", Box::new(ExampleAsyncResolver));
+        assert!(registry.methods().is_empty());
+        assert!(registry.try_resolve("did:example:123").is_err());
+        assert!(registry.try_resolve_async("did:example:123").await.is_ok());
+    }
+
+    #[test]
+    fn resolution_error_codes_test() {
+        let registry = ResolverRegistry::new();
+        assert!(matches!(
+            registry.try_resolve("did:example:123"),
+            Err(Error::MethodNotSupported(_))
+        ));
+        assert!(matches!(
+            registry.try_resolve("idd:example:123"),
+            Err(Error::InvalidDidUrl(_))
+        ));
+        let res = registry.resolve_with_metadata("did:example:123");
+        assert_eq!(
+            res.did_resolution_metadata.error,
+            Some("methodNotSupported".into())
+        );
+        assert_eq!(
+            Error::RepresentationNotSupported("application/did+cbor".into()).code(),
+            "representationNotSupported"
+        );
+        assert_eq!(Error::InvalidKel("no events".into()).code(), "invalidDid");
+    }
+
+    #[test]
+    fn default_registry_test() {
+        let registry = ResolverRegistry::default();
+        let methods = registry.methods();
+        #[cfg(feature = "didkey")]
+        assert!(methods.contains(&"key"));
+        #[cfg(feature = "keriox")]
+        assert!(methods.contains(&"keri"));
+        #[cfg(feature = "jolo")]
+        assert!(methods.contains(&"jolo"));
+        assert!(!methods.contains(&"example"));
+    }
+
+    #[cfg(feature = "jolo")]
+    #[test]
+    fn jolo_registration_test() {
+        let registry = ResolverRegistry::with_jolo(&JoloConfig::bundled().unwrap()).unwrap();
+        assert!(registry.methods().contains(&"jolo"));
+        assert!(matches!(
+            registry.try_resolve("did:jolo:123"),
+            Err(Error::NotDidJolo)
+        ));
+        let mut registry = ResolverRegistry::new();
+        registry.register_jolo(Err(Error::IoError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no such file",
+        ))));
+        assert_eq!(registry.methods(), vec!["jolo"]);
+        match registry.try_resolve("did:jolo:123") {
+            Err(Error::ConfigOpenError(reason)) => {
+                assert_eq!(reason, "no such file")
+            }
+            _ => panic!("misconfigured did:jolo must report config error"),
+        }
+        let res = registry.resolve_with_metadata("did:jolo:123");
+        assert_eq!(
+            res.did_resolution_metadata.error,
+            Some("internalError".into())
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{Document, Error};
+use serde::{Deserialize, Serialize};
+
+/// Media type of the documents produced by resolvers of this crate.
+///
+pub const DID_LD_JSON: &str = "application/did+ld+json";
+
+/// Full output of DID resolution as defined by the spec:
+///     https://w3c-ccg.github.io/did-resolution/#did-resolution-result
+/// Document is `None` if resolution failed, in which case
+///     `did_resolution_metadata.error` holds the error code.
+///
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionResult {
+    pub did_resolution_metadata: ResolutionMetadata,
+    pub did_document: Option<Document>,
+    pub did_document_metadata: DocumentMetadata,
+}
+
+/// Metadata about the resolution process itself.
+/// https://www.w3.org/TR/did-core/#did-resolution-metadata
+///
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Metadata about the resolved document, filled by each resolver
+///     with whatever the method can tell.
+/// https://www.w3.org/TR/did-core/#did-document-metadata
+///
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deactivated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_update: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_version_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equivalent_id: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_id: Option<String>,
+}
+
+impl ResolutionResult {
+    /// Successful resolution result without any document metadata.
+    ///
+    pub fn from_document(document: Document) -> Self {
+        Self {
+            did_resolution_metadata: ResolutionMetadata {
+                content_type: Some(DID_LD_JSON.into()),
+                error: None,
+            },
+            did_document: Some(document),
+            did_document_metadata: DocumentMetadata::default(),
+        }
+    }
+
+    /// Failed resolution result with error code of `error`.
+    /// `Error::Deactivated` is also reflected in document metadata.
+    ///
+    pub fn from_error(error: &Error) -> Self {
+        Self {
+            did_resolution_metadata: ResolutionMetadata {
+                content_type: None,
+                error: Some(error.code().into()),
+            },
+            did_document: None,
+            did_document_metadata: DocumentMetadata {
+                deactivated: match error {
+                    Error::Deactivated(_) => Some(true),
+                    _ => None,
+                },
+                ..Default::default()
+            },
+        }
+    }
+
+    /// `true` if document was resolved.
+    ///
+    pub fn is_ok(&self) -> bool {
+        self.did_document.is_some()
+    }
+}
+
+impl From<Result<Document, Error>> for ResolutionResult {
+    fn from(result: Result<Document, Error>) -> Self {
+        match result {
+            Ok(document) => Self::from_document(document),
+            Err(e) => Self::from_error(&e),
+        }
+    }
+}
+
+impl From<Error> for ResolutionResult {
+    fn from(e: Error) -> Self {
+        Self::from_error(&e)
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{document::document_from_json, DdoResolver, DidUrl, Document, Error, Service};
+use serde_json::Value;
+
+/// HTTP client used by `DidWebResolver` to fetch documents.
+/// Default implementation is `UreqClient`, custom ones can be used to
+///     route requests through proxies or to serve documents in tests.
+///
+pub trait HttpClient {
+    /// Fetches body of `url` with GET request.
+    /// Missing resources (404, 410) must be reported as `Error::NotFound`.
+    ///
+    fn get(&self, url: &str) -> Result<String, Error>;
+}
+
+/// Blocking `HttpClient` based on `ureq` with TLS certificates
+///     verified against webpki roots.
+///
+pub struct UreqClient {
+    agent: ureq::Agent,
+}
+
+impl UreqClient {
+    pub fn new() -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(std::time::Duration::from_secs(30))
+                .build(),
+        }
+    }
+}
+
+impl Default for UreqClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpClient for UreqClient {
+    fn get(&self, url: &str) -> Result<String, Error> {
+        match self.agent.get(url).call() {
+            Ok(response) => response
+                .into_string()
+                .map_err(|e| Error::HttpError(e.to_string())),
+            Err(ureq::Error::Status(404, _)) | Err(ureq::Error::Status(410, _)) => {
+                Err(Error::NotFound(url.into()))
+            }
+            Err(e) => Err(Error::HttpError(e.to_string())),
+        }
+    }
+}
+
+/// `did:web` resolver, which fetches documents over HTTPS as defined by
+///     https://w3c-ccg.github.io/did-method-web/#read-resolve
+/// Registered for `did:web` in `ResolverRegistry::default()`. Fetching
+///     is blocking, asynchronous resolution of the registry runs it on
+///     blocking thread pool.
+/// Available ONLY with `didweb` feature
+///
+pub struct DidWebResolver<C: HttpClient = UreqClient> {
+    client: C,
+}
+
+impl DidWebResolver {
+    /// Resolver with default `UreqClient`.
+    ///
+    pub fn new() -> Self {
+        Self::with_client(UreqClient::new())
+    }
+}
+
+impl Default for DidWebResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: HttpClient> DidWebResolver<C> {
+    /// Resolver which fetches documents with `client`.
+    ///
+    pub fn with_client(client: C) -> Self {
+        Self { client }
+    }
+
+    // Fetches document of `did_url` and checks that it is the one of its DID
+    fn fetch(&self, did_url: &str) -> Result<(Document, String), Error> {
+        let did = did_url.parse::<DidUrl>()?.did();
+        let json = self.client.get(&did_web_url(&did)?)?;
+        let document = document_from_json(&json)?;
+        if document.id != did {
+            return Err(Error::InvalidDocument(format!(
+                "document id {} does not match {}",
+                document.id, did
+            )));
+        }
+        Ok((document, json))
+    }
+}
+
+impl<C: HttpClient> DdoResolver for DidWebResolver<C> {
+    fn resolve(&self, did_url: &str) -> Result<Document, Error> {
+        Ok(self.fetch(did_url)?.0)
+    }
+
+    /// Services from `service` section of the fetched document.
+    ///
+    fn services(&self, did_url: &str) -> Result<Vec<Service>, Error> {
+        let value: Value = serde_json::from_str(&self.fetch(did_url)?.1)?;
+        match value.get("service") {
+            Some(services) => Ok(serde_json::from_value(services.clone())?),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+/// Transforms `did:web` DID into HTTPS url of its document:
+///     `did:web:example.com` -> `https://example.com/.well-known/did.json`,
+///     `did:web:example.com%3A3000:user:alice` ->
+///     `https://example.com:3000/user/alice/did.json`
+/// Only `%3A` (port separator) is decoded in the domain, which must be
+///     a plain host name otherwise. Path segments are kept percent
+///     encoded, so they can not inject anything into the url.
+///
+pub fn did_web_url(did: &str) -> Result<String, Error> {
+    let url: DidUrl = did.parse()?;
+    if url.method != "web" {
+        return Err(Error::InvalidDid(format!("not a did:web: {}", did)));
+    }
+    let invalid = || Error::InvalidDid(format!("invalid did:web: {}", did));
+    let mut segments = url.method_specific_id.split(':');
+    let domain = segments
+        .next()
+        .unwrap_or_default()
+        .replace("%3A", ":")
+        .replace("%3a", ":");
+    let (host, port) = match domain.split_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (domain.as_str(), None),
+    };
+    if host.is_empty()
+        || !host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        || port.is_some_and(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(invalid());
+    }
+    let path = segments.collect::<Vec<&str>>();
+    if path.iter().any(|segment| segment.is_empty()) {
+        return Err(invalid());
+    }
+    if path.is_empty() {
+        Ok(format!("https://{}/.well-known/did.json", domain))
+    } else {
+        Ok(format!("https://{}/{}/did.json", domain, path.join("/")))
+    }
+}
+
+#[cfg(test)]
+mod did_web_tests {
+    use super::*;
+    use crate::{registry::ResolverRegistry, DdoParser, DereferencedResource, Relationship};
+    use std::{
+        collections::HashMap,
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    const DOCUMENT: &str = r##"{
+        "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/suites/jws-2020/v1"],
+        "id": "did:web:example.com",
+        "verificationMethod": [{
+            "id": "did:web:example.com#key-0",
+            "type": "JsonWebKey2020",
+            "controller": "did:web:example.com",
+            "publicKeyJwk": {
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "x": "0-e2i2_Ua1S5HbTYnVB0lj2Z2ytXu2-tYmDFf8f5NjU"
+            }
+        }],
+        "authentication": ["did:web:example.com#key-0"],
+        "assertionMethod": ["#key-0"],
+        "service": [{"id": "#linked-domain", "type": "LinkedDomains", "serviceEndpoint": "https://example.com"}]
+    }"##;
+
+    // Stand-in for the web server of `example.com`, which serves `documents`
+    //  by path over plain HTTP on local port.
+    fn serve(documents: HashMap<&'static str, String>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(&stream);
+                reader.read_line(&mut request_line).unwrap();
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let path = request_line.split(' ').nth(1).unwrap_or_default();
+                let (status, body) = match documents.get(path) {
+                    Some(body) => ("200 OK", body.as_str()),
+                    None => ("404 Not Found", ""),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        port
+    }
+
+    // Sends all requests to local server instead of the DID's domain
+    struct LocalClient {
+        port: u16,
+        inner: UreqClient,
+    }
+
+    impl HttpClient for LocalClient {
+        fn get(&self, url: &str) -> Result<String, Error> {
+            let path = url.trim_start_matches("https://");
+            let path = &path[path.find('/').unwrap_or(path.len())..];
+            self.inner
+                .get(&format!("http://127.0.0.1:{}{}", self.port, path))
+        }
+    }
+
+    fn local_resolver() -> DidWebResolver<LocalClient> {
+        let mut documents = HashMap::new();
+        documents.insert("/.well-known/did.json", DOCUMENT.to_string());
+        documents.insert(
+            "/user/alice/did.json",
+            DOCUMENT.replace("did:web:example.com", "did:web:example.com:user:alice"),
+        );
+        documents.insert("/user/mallory/did.json", DOCUMENT.to_string());
+        documents.insert("/user/broken/did.json", "{\"id\": 42}".to_string());
+        DidWebResolver::with_client(LocalClient {
+            port: serve(documents),
+            inner: UreqClient::new(),
+        })
+    }
+
+    #[test]
+    fn did_web_url_test() {
+        assert_eq!(
+            did_web_url("did:web:w3c-ccg.github.io").unwrap(),
+            "https://w3c-ccg.github.io/.well-known/did.json"
+        );
+        assert_eq!(
+            did_web_url("did:web:w3c-ccg.github.io:user:alice").unwrap(),
+            "https://w3c-ccg.github.io/user/alice/did.json"
+        );
+        assert_eq!(
+            did_web_url("did:web:example.com%3A3000:user:alice").unwrap(),
+            "https://example.com:3000/user/alice/did.json"
+        );
+        assert!(did_web_url("did:key:z6Mk").is_err());
+        assert!(did_web_url("did:web:example.com%2Fuser").is_err());
+        for did in [
+            "did:web:example.com%40evil.com",
+            "did:web:evil.com%3Fexample.com",
+            "did:web:evil.com%23example.com",
+            "did:web:evil.com%5Cexample.com",
+            "did:web:example.com%3Ahttp",
+            "did:web:example.com::alice",
+        ] {
+            assert!(did_web_url(did).is_err(), "{} should be rejected", did);
+        }
+        assert_eq!(
+            did_web_url("did:web:example.com:user%3Fid%3D1").unwrap(),
+            "https://example.com/user%3Fid%3D1/did.json"
+        );
+    }
+
+    #[test]
+    fn resolve_from_local_server_test() {
+        let resolver = local_resolver();
+        let doc = resolver.resolve("did:web:example.com").unwrap();
+        assert_eq!(doc.id, "did:web:example.com");
+        assert_eq!(doc.context, "https://www.w3.org/ns/did/v1");
+        assert_eq!(
+            doc.find_public_key_for_curve("Ed25519").map(|k| k.len()),
+            Some(32)
+        );
+        assert!(doc.is_authorized_for("#key-0", Relationship::AssertionMethod));
+        let alice = resolver
+            .resolve("did:web:example.com:user:alice#key-0")
+            .unwrap();
+        assert_eq!(alice.id, "did:web:example.com:user:alice");
+    }
+
+    #[test]
+    fn reject_invalid_documents_test() {
+        let resolver = local_resolver();
+        assert!(matches!(
+            resolver.resolve("did:web:example.com:user:mallory"),
+            Err(Error::InvalidDocument(_))
+        ));
+        assert!(matches!(
+            resolver.resolve("did:web:example.com:user:broken"),
+            Err(Error::InvalidDocument(_))
+        ));
+        assert!(matches!(
+            resolver.resolve("did:web:example.com:user:bob"),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn registry_resolution_test() {
+        let mut registry = ResolverRegistry::new();
+        registry.register("web", Box::new(local_resolver()));
+        let res = registry.resolve_with_metadata("did:web:example.com:user:bob");
+        assert_eq!(res.did_resolution_metadata.error, Some("notFound".into()));
+        assert!(registry.resolve("did:web:example.com").is_some());
+        assert!(ResolverRegistry::default().methods().contains(&"web"));
+    }
+
+    #[test]
+    fn dereference_service_test() {
+        let mut registry = ResolverRegistry::new();
+        registry.register("web", Box::new(local_resolver()));
+        match registry
+            .dereference("did:web:example.com#linked-domain")
+            .content_stream
+        {
+            Some(DereferencedResource::Service(service)) => {
+                assert_eq!(service.service_type, "LinkedDomains");
+                assert_eq!(service.service_endpoint, "https://example.com");
+            }
+            other => panic!("unexpected content: {:?}", other),
+        }
+        let alice = local_resolver()
+            .services("did:web:example.com:user:alice")
+            .unwrap();
+        assert_eq!(alice[0].id, "#linked-domain");
+        let res = registry.dereference("did:web:example.com#unknown");
+        assert_eq!(res.dereferencing_metadata.error, Some("notFound".into()));
+    }
+}